@@ -0,0 +1,270 @@
+//! Render a region of the Mandelbrot set to an image file.
+//!
+//! This is the program from Chapter 2, rewritten to use this crate's
+//! `Complex` type, so it doubles as a workout for its arithmetic operators.
+//! The image is written as a PNG file, or as a binary PGM file if the file
+//! name ends in `.pgm`.
+//!
+//! Writing PNG files needs the `png` feature: `cargo run --features png --bin
+//! mandelbrot ...`. Without it, the program can only write PGM files.
+
+#![warn(rust_2018_idioms)]
+#![allow(elided_lifetimes_in_paths)]
+
+use complex::Complex;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "png")]
+use png::{BitDepth, ColorType, Encoder};
+
+/// Try to determine if `c` is in the Mandelbrot set, using at most `limit`
+/// iterations to decide.
+///
+/// If `c` is not a member, return `Some(i)`, where `i` is the number of
+/// iterations it took for `c` to leave the circle of radius two centered on the
+/// origin. If `c` seems to be a member (more precisely, if we reached the
+/// iteration limit without being able to prove that `c` is not a member),
+/// return `None`.
+fn escape_time(c: Complex<f64>, limit: usize) -> Option<usize> {
+    let mut z = Complex { re: 0.0, im: 0.0 };
+    for i in 0..limit {
+        if z.re * z.re + z.im * z.im > 4.0 {
+            return Some(i);
+        }
+        z = z * z + c;
+    }
+
+    None
+}
+
+/// Parse the string `s` as a coordinate pair, like `"400x600"` or `"1.0,0.5"`.
+///
+/// Specifically, `s` should have the form <left><sep><right>, where <sep> is
+/// the character given by the `separator` argument, and <left> and <right> are
+/// both strings that can be parsed by `T::from_str`. `separator` must be an
+/// ASCII character.
+///
+/// If `s` has the proper form, return `Some<(x, y)>`. If it doesn't parse
+/// correctly, return `None`.
+fn parse_pair<T: FromStr>(s: &str, separator: char) -> Option<(T, T)> {
+    match s.find(separator) {
+        None => None,
+        Some(index) => match (T::from_str(&s[..index]), T::from_str(&s[index + 1..])) {
+            (Ok(l), Ok(r)) => Some((l, r)),
+            _ => None,
+        },
+    }
+}
+
+#[test]
+fn test_parse_pair() {
+    assert_eq!(parse_pair::<i32>("", ','), None);
+    assert_eq!(parse_pair::<i32>("10,", ','), None);
+    assert_eq!(parse_pair::<i32>(",10", ','), None);
+    assert_eq!(parse_pair::<i32>("10,20", ','), Some((10, 20)));
+    assert_eq!(parse_pair::<i32>("10,20xy", ','), None);
+    assert_eq!(parse_pair::<f64>("0.5x", 'x'), None);
+    assert_eq!(parse_pair::<f64>("0.5x1.5", 'x'), Some((0.5, 1.5)));
+}
+
+/// Parse a complex number, either as a pair of floating-point numbers
+/// separated by a comma, as in the book, or in the notation `Complex`'s
+/// `FromStr` implementation accepts, like `-1.2+0.35i`.
+fn parse_complex(s: &str) -> Option<Complex<f64>> {
+    match parse_pair(s, ',') {
+        Some((re, im)) => Some(Complex { re, im }),
+        None => s.parse().ok(),
+    }
+}
+
+#[test]
+fn test_parse_complex() {
+    assert_eq!(parse_complex("1.25,-0.0625"),
+               Some(Complex { re: 1.25, im: -0.0625 }));
+    assert_eq!(parse_complex("1.25-0.0625i"),
+               Some(Complex { re: 1.25, im: -0.0625 }));
+    assert_eq!(parse_complex(",-0.0625"), None);
+}
+
+/// Given the row and column of a pixel in the output image, return the
+/// corresponding point on the complex plane.
+///
+/// `bounds` is a pair giving the width and height of the image in pixels.
+/// `pixel` is a (column, row) pair indicating a particular pixel in that image.
+/// The `upper_left` and `lower_right` parameters are points on the complex
+/// plane designating the area our image covers.
+fn pixel_to_point(bounds: (usize, usize),
+                  pixel: (usize, usize),
+                  upper_left: Complex<f64>,
+                  lower_right: Complex<f64>)
+    -> Complex<f64>
+{
+    let (width, height) = (lower_right.re - upper_left.re,
+                           upper_left.im - lower_right.im);
+    Complex {
+        re: upper_left.re + pixel.0 as f64 * width  / bounds.0 as f64,
+        im: upper_left.im - pixel.1 as f64 * height / bounds.1 as f64
+        // Why subtraction here? pixel.1 increases as we go down,
+        // but the imaginary component increases as we go up.
+    }
+}
+
+#[test]
+fn test_pixel_to_point() {
+    assert_eq!(pixel_to_point((100, 200), (25, 175),
+                              Complex { re: -1.0, im:  1.0 },
+                              Complex { re:  1.0, im: -1.0 }),
+               Complex { re: -0.5, im: -0.75 });
+}
+
+/// Render a rectangle of the Mandelbrot set into a buffer of pixels.
+///
+/// The `bounds` argument gives the width and height of the buffer `pixels`,
+/// which holds one grayscale pixel per byte. The `upper_left` and `lower_right`
+/// arguments specify points on the complex plane corresponding to the upper-
+/// left and lower-right corners of the pixel buffer.
+fn render(pixels: &mut [u8],
+          bounds: (usize, usize),
+          upper_left: Complex<f64>,
+          lower_right: Complex<f64>)
+{
+    assert!(pixels.len() == bounds.0 * bounds.1);
+
+    for row in 0..bounds.1 {
+        for column in 0..bounds.0 {
+            let point = pixel_to_point(bounds, (column, row),
+                                       upper_left, lower_right);
+            pixels[row * bounds.0 + column] =
+                match escape_time(point, 255) {
+                    None => 0,
+                    Some(count) => 255 - count as u8
+                };
+        }
+    }
+}
+
+/// Render the image in horizontal bands, one per thread.
+fn render_parallel(pixels: &mut [u8],
+                   bounds: (usize, usize),
+                   upper_left: Complex<f64>,
+                   lower_right: Complex<f64>,
+                   threads: usize)
+{
+    let rows_per_band = bounds.1 / threads + 1;
+    let bands: Vec<&mut [u8]> = pixels.chunks_mut(rows_per_band * bounds.0).collect();
+
+    std::thread::scope(|spawner| {
+        for (i, band) in bands.into_iter().enumerate() {
+            let top = rows_per_band * i;
+            let height = band.len() / bounds.0;
+            let band_bounds = (bounds.0, height);
+            let band_upper_left = pixel_to_point(bounds, (0, top),
+                                                 upper_left, lower_right);
+            let band_lower_right = pixel_to_point(bounds, (bounds.0, top + height),
+                                                  upper_left, lower_right);
+
+            spawner.spawn(move || {
+                render(band, band_bounds, band_upper_left, band_lower_right);
+            });
+        }
+    });
+}
+
+#[test]
+fn test_render_parallel() {
+    // Splitting the work into bands mustn't change the image.
+    let bounds = (61, 47);
+    let (upper_left, lower_right) = (Complex { re: -2.0, im: 1.2 },
+                                     Complex { re: 0.6, im: -1.2 });
+
+    let mut serial = vec![0; bounds.0 * bounds.1];
+    render(&mut serial, bounds, upper_left, lower_right);
+
+    for &threads in &[1, 2, 5, 8] {
+        let mut parallel = vec![0; bounds.0 * bounds.1];
+        render_parallel(&mut parallel, bounds, upper_left, lower_right, threads);
+        assert!(serial == parallel, "{} threads rendered differently", threads);
+    }
+}
+
+/// Return true if we can render and save an image of size `bounds`:
+/// `render_parallel` needs at least one column, an empty image is useless
+/// anyway, and PNG dimensions are 32-bit.
+fn valid_bounds(bounds: (usize, usize)) -> bool {
+    let fits = |n: usize| n > 0 && u32::try_from(n).is_ok();
+    fits(bounds.0) && fits(bounds.1) && bounds.0.checked_mul(bounds.1).is_some()
+}
+
+#[test]
+fn test_valid_bounds() {
+    assert!(valid_bounds((1000, 750)));
+    assert!(!valid_bounds((0, 100)));
+    assert!(!valid_bounds((100, 0)));
+    assert!(!valid_bounds((1 << 32, 1)));
+}
+
+/// Write the buffer `pixels`, whose dimensions are given by `bounds`, to the
+/// file named `filename`.
+fn write_image(filename: &str, pixels: &[u8], bounds: (usize, usize))
+    -> Result<(), Box<dyn Error>>
+{
+    if filename.ends_with(".pgm") {
+        let mut output = BufWriter::new(File::create(filename)?);
+        write!(output, "P5\n{} {}\n255\n", bounds.0, bounds.1)?;
+        output.write_all(pixels)?;
+        output.flush()?;
+        return Ok(());
+    }
+
+    #[cfg(feature = "png")]
+    {
+        let output = BufWriter::new(File::create(filename)?);
+        let mut encoder = Encoder::new(output, bounds.0 as u32, bounds.1 as u32);
+        encoder.set_color(ColorType::Grayscale);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.write_header()?.write_image_data(pixels)?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "png"))]
+    Err("writing PNG files needs the `png` feature; use a `.pgm` file name instead".into())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.len() != 5 && args.len() != 6 {
+        eprintln!("Usage: {} FILE PIXELS UPPERLEFT LOWERRIGHT [THREADS]",
+                  args[0]);
+        eprintln!("Example: {} mandel.png 1000x750 -1.20,0.35 -1,0.20",
+                  args[0]);
+        std::process::exit(1);
+    }
+
+    let bounds: (usize, usize) = parse_pair(&args[2], 'x')
+        .expect("error parsing image dimensions");
+    if !valid_bounds(bounds) {
+        eprintln!("image dimensions must be positive, and fit in a PNG file: {}", args[2]);
+        std::process::exit(1);
+    }
+    let upper_left = parse_complex(&args[3])
+        .expect("error parsing upper left corner point");
+    let lower_right = parse_complex(&args[4])
+        .expect("error parsing lower right corner point");
+    let threads = match args.get(5) {
+        Some(arg) => arg.parse().expect("error parsing thread count"),
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    assert!(threads > 0, "thread count must be positive");
+
+    let mut pixels = vec![0; bounds.0 * bounds.1];
+
+    render_parallel(&mut pixels, bounds, upper_left, lower_right, threads);
+
+    write_image(&args[1], &pixels, bounds)
+        .expect("error writing image file");
+}
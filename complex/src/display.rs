@@ -0,0 +1,191 @@
+//! Formatting `Complex<f32>` and `Complex<f64>` values.
+//!
+//! These follow the `complex_fancy` example in the `formatting` module: `{}`
+//! prints rectangular notation, like `-0.5 + 0.866i`, and `{:#}` prints polar
+//! notation with the angle in degrees, like `2 ∠ 90°`. `Polar` values print in
+//! polar notation either way.
+//!
+//! The usual flags are honored:
+//!
+//! - A precision, as in `{:.3}`, applies to each number printed.
+//! - The `+` flag forces a sign on the real part, or on the magnitude.
+//! - A width, fill and alignment, as in `{:>20}`, pad the whole value, which is
+//!   aligned to the right by default, like other numbers.
+//! - `{:e}` and `{:E}` print each number in scientific notation.
+
+use super::{Complex, Polar};
+use std::fmt::{self, Alignment, Display, Formatter, LowerExp, UpperExp, Write};
+
+#[derive(Clone, Copy)]
+enum Notation {
+    Plain,
+    LowerExp,
+    UpperExp,
+}
+
+/// Format a single number with the given precision, and a leading `+` if
+/// `plus` is set and the number isn't negative.
+fn number<T>(x: T, notation: Notation, precision: Option<usize>, plus: bool) -> String
+where
+    T: Display + LowerExp + UpperExp,
+{
+    let s = match (notation, precision) {
+        (Notation::Plain, None) => format!("{}", x),
+        (Notation::Plain, Some(p)) => format!("{:.*}", p, x),
+        (Notation::LowerExp, None) => format!("{:e}", x),
+        (Notation::LowerExp, Some(p)) => format!("{:.*e}", p, x),
+        (Notation::UpperExp, None) => format!("{:E}", x),
+        (Notation::UpperExp, Some(p)) => format!("{:.*E}", p, x),
+    };
+    if plus && !s.starts_with('-') {
+        format!("+{}", s)
+    } else {
+        s
+    }
+}
+
+/// Write `s` to `f`, padded to `f`'s width with its fill and alignment.
+fn pad(f: &mut Formatter, s: &str) -> fmt::Result {
+    let len = s.chars().count();
+    let padding = match f.width() {
+        Some(width) if width > len => width - len,
+        _ => return f.write_str(s),
+    };
+
+    let (before, after) = match f.align() {
+        Some(Alignment::Left) => (0, padding),
+        Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+        Some(Alignment::Right) | None => (padding, 0),
+    };
+
+    let fill = f.fill();
+    for _ in 0..before {
+        f.write_char(fill)?;
+    }
+    f.write_str(s)?;
+    for _ in 0..after {
+        f.write_char(fill)?;
+    }
+    Ok(())
+}
+
+macro_rules! define_display {
+    ($float:ident) => {
+        impl Complex<$float> {
+            fn write(&self, f: &mut Formatter, notation: Notation) -> fmt::Result {
+                if f.alternate() {
+                    return self.to_polar().write(f, notation);
+                }
+
+                // Use the sign bit, so that `-0.0` prints as `- 0i`, and parses
+                // back to the same value.
+                let im_sign = if self.im.is_sign_negative() { '-' } else { '+' };
+                let s = format!(
+                    "{} {} {}i",
+                    number(self.re, notation, f.precision(), f.sign_plus()),
+                    im_sign,
+                    number(self.im.abs(), notation, f.precision(), false),
+                );
+                pad(f, &s)
+            }
+        }
+
+        impl Polar<$float> {
+            fn write(&self, f: &mut Formatter, notation: Notation) -> fmt::Result {
+                let s = format!(
+                    "{} ∠ {}°",
+                    number(self.r, notation, f.precision(), f.sign_plus()),
+                    number(self.theta.to_degrees(), notation, f.precision(), false),
+                );
+                pad(f, &s)
+            }
+        }
+
+        impl Display for Complex<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::Plain)
+            }
+        }
+
+        impl LowerExp for Complex<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::LowerExp)
+            }
+        }
+
+        impl UpperExp for Complex<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::UpperExp)
+            }
+        }
+
+        impl Display for Polar<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::Plain)
+            }
+        }
+
+        impl LowerExp for Polar<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::LowerExp)
+            }
+        }
+
+        impl UpperExp for Polar<$float> {
+            fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+                self.write(f, Notation::UpperExp)
+            }
+        }
+    };
+}
+
+define_display!(f32);
+define_display!(f64);
+
+#[test]
+fn test_display() {
+    // The same results as the examples in the `formatting` module.
+    assert_eq!(format!("{}", Complex::new(-0.5, 0.866)), "-0.5 + 0.866i");
+    assert_eq!(format!("{}", Complex::new(-0.5, -0.866)), "-0.5 - 0.866i");
+    assert_eq!(format!("{}", Complex::new(0.0, 2.0)), "0 + 2i");
+    assert_eq!(format!("{:#}", Complex::new(0.0, 2.0)), "2 ∠ 90°");
+    assert_eq!(format!("{}", Complex::new(1.0, -0.0)), "1 - 0i");
+    assert_eq!(format!("{}", Complex::new(1.5f32, 2.0)), "1.5 + 2i");
+}
+
+#[test]
+fn test_display_flags() {
+    let third = Complex::new(-0.5, f64::sqrt(0.75));
+    assert_eq!(format!("{:.3}", third), "-0.500 + 0.866i");
+    assert_eq!(format!("{:.1}", Complex::new(2.0, -1.0)), "2.0 - 1.0i");
+    assert_eq!(format!("{:+}", Complex::new(2.0, 1.0)), "+2 + 1i");
+    assert_eq!(format!("{:+.2}", Complex::new(2.0, -1.0)), "+2.00 - 1.00i");
+
+    assert_eq!(format!("{:>15.2}", third), "  -0.50 + 0.87i");
+    assert_eq!(format!("{:15.2}", third), "  -0.50 + 0.87i");
+    assert_eq!(format!("{:<16.2}|", third), "-0.50 + 0.87i   |");
+    assert_eq!(format!("{:*^17.2}", third), "**-0.50 + 0.87i**");
+    assert_eq!(format!("{:4}", third), "-0.5 + 0.8660254037844386i");
+
+    assert_eq!(format!("{:e}", Complex::new(1500.0, -0.25)), "1.5e3 - 2.5e-1i");
+    assert_eq!(format!("{:.2E}", Complex::new(1500.0, 0.25)), "1.50E3 + 2.50E-1i");
+
+    assert_eq!(format!("{:#.3}", Complex::new(0.0, 2.0)), "2.000 ∠ 90.000°");
+    assert_eq!(format!("{:>#12}", Complex::new(0.0, 2.0)), "     2 ∠ 90°");
+    assert_eq!(format!("{:.1}", Polar::new(1.0, std::f64::consts::PI)), "1.0 ∠ 180.0°");
+}
+
+#[test]
+fn test_display_parse_round_trip() {
+    let values = [
+        Complex::new(-0.5, 0.866),
+        Complex::new(1e-300, -2.5e10),
+        Complex::new(3.0, -0.0),
+    ];
+    for z in values {
+        let back: Complex<f64> = z.to_string().parse().unwrap();
+        assert_eq!(back, z);
+        assert!(back.im.is_sign_negative() == z.im.is_sign_negative());
+        assert_eq!(format!("{:e}", z).parse::<Complex<f64>>(), Ok(z));
+    }
+}
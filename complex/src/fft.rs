@@ -0,0 +1,367 @@
+//! Fast Fourier transforms over slices of `Complex<f64>`.
+//!
+//! The forward transform of `x` is `X[k] = Σ x[j] e^(-2πijk/n)`, and the
+//! inverse transform divides by `n`, so that `inverse` undoes `forward`
+//! exactly, up to rounding.
+//!
+//! Both functions accept slices of any length. Lengths that are powers of two
+//! use an iterative radix-2 transform. Other lengths whose prime factors are
+//! all small use an iterative mixed-radix transform, and anything else uses
+//! Bluestein's algorithm, which recasts the transform as a convolution
+//! computed with power-of-two transforms. All take O(n log n) time.
+
+use super::Complex;
+use std::f64::consts::PI;
+
+/// The largest prime factor the mixed-radix transform handles directly.
+/// Lengths with larger prime factors go to Bluestein's algorithm.
+const MAX_DIRECT_RADIX: usize = 16;
+
+/// Which way a transform goes. The value is the sign of the exponent.
+#[derive(Clone, Copy)]
+enum Direction {
+    Forward,
+    Inverse,
+}
+
+impl Direction {
+    fn sign(self) -> f64 {
+        match self {
+            Direction::Forward => -1.0,
+            Direction::Inverse => 1.0,
+        }
+    }
+}
+
+/// Replace `data` with its discrete Fourier transform.
+pub fn forward(data: &mut [Complex<f64>]) {
+    transform(data, Direction::Forward);
+}
+
+/// Replace `data` with its inverse discrete Fourier transform, including
+/// the division by `data.len()`.
+pub fn inverse(data: &mut [Complex<f64>]) {
+    transform(data, Direction::Inverse);
+    let scale = 1.0 / data.len() as f64;
+    for z in data.iter_mut() {
+        *z = Complex::new(z.re * scale, z.im * scale);
+    }
+}
+
+/// Return the transform of the real sequence `input`.
+///
+/// A real sequence's transform is conjugate-symmetric, `X[n - k] ==
+/// X[k].conj()`, so only the first `input.len() / 2 + 1` elements are
+/// returned; the rest carry no further information. An empty input has an
+/// empty transform.
+pub fn forward_real(input: &[f64]) -> Vec<Complex<f64>> {
+    let mut data: Vec<Complex<f64>> = input.iter().map(|&x| Complex::new(x, 0.0)).collect();
+    forward(&mut data);
+    data.truncate(real_spectrum_len(input.len()));
+    data
+}
+
+/// Return the real sequence of length `n` whose transform begins with
+/// `spectrum`, as produced by `forward_real`.
+///
+/// Panics if `spectrum.len()` is not `n / 2 + 1`, or zero when `n` is.
+pub fn inverse_real(spectrum: &[Complex<f64>], n: usize) -> Vec<f64> {
+    assert_eq!(spectrum.len(), real_spectrum_len(n),
+               "spectrum of a real sequence of length {} must have {} elements",
+               n, real_spectrum_len(n));
+
+    let mut data = Vec::with_capacity(n);
+    data.extend_from_slice(&spectrum[..n.min(spectrum.len())]);
+    for k in data.len()..n {
+        data.push(spectrum[n - k].conj());
+    }
+    inverse(&mut data);
+    data.into_iter().map(|z| z.re).collect()
+}
+
+/// The length of `forward_real`'s result for an input of length `n`.
+fn real_spectrum_len(n: usize) -> usize {
+    if n == 0 { 0 } else { n / 2 + 1 }
+}
+
+fn transform(data: &mut [Complex<f64>], direction: Direction) {
+    let n = data.len();
+    if n <= 1 {
+        return;
+    }
+
+    if n.is_power_of_two() {
+        radix2(data, direction);
+    } else if prime_factors(n).iter().all(|&p| p <= MAX_DIRECT_RADIX) {
+        mixed_radix(data, direction);
+    } else {
+        bluestein(data, direction);
+    }
+}
+
+/// Return `e^(±2πij/n)` for `j` in `0..count`.
+fn twiddles(n: usize, count: usize, direction: Direction) -> Vec<Complex<f64>> {
+    let step = direction.sign() * 2.0 * PI / n as f64;
+    (0..count)
+        .map(|j| Complex::<f64>::from_polar(1.0, step * j as f64))
+        .collect()
+}
+
+/// Return the prime factors of `n`, smallest first, with repetition.
+fn prime_factors(mut n: usize) -> Vec<usize> {
+    let mut factors = Vec::new();
+    let mut p = 2;
+    while p * p <= n {
+        while n.is_multiple_of(p) {
+            factors.push(p);
+            n /= p;
+        }
+        p += 1;
+    }
+    if n > 1 {
+        factors.push(n);
+    }
+    factors
+}
+
+/// Transform `data` in place. Its length must be a power of two.
+fn radix2(data: &mut [Complex<f64>], direction: Direction) {
+    let n = data.len();
+    let bits = n.trailing_zeros();
+
+    // Put the elements in bit-reversed order, so that each pass below can
+    // combine adjacent blocks.
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    let twiddles = twiddles(n, n / 2, direction);
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        let stride = n / len;
+        for block in data.chunks_exact_mut(len) {
+            for k in 0..half {
+                let u = block[k];
+                let v = block[k + half] * twiddles[k * stride];
+                block[k] = u + v;
+                block[k + half] = u - v;
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Transform `data` in place. Its length's prime factors must all be at most
+/// `MAX_DIRECT_RADIX`.
+///
+/// This is the radix-2 algorithm generalized to mixed radices. With `n`
+/// factored as `p₁ p₂ … pₖ`, smallest first, the transform splits the input
+/// into `p₁` interleaved subsequences, transforms each, and combines them;
+/// the subsequences split by `p₂` in turn, and so on. Permuting the input so
+/// that each subsequence's elements are contiguous lets every level combine
+/// adjacent blocks in place, innermost level first.
+fn mixed_radix(data: &mut [Complex<f64>], direction: Direction) {
+    let n = data.len();
+    let factors = prime_factors(n);
+
+    // Element `j` of the input belongs at the position whose mixed-radix
+    // digits are those of `j` reversed. Move each cycle of that permutation
+    // in turn, marking the elements already moved.
+    let position = |mut j: usize| {
+        let (mut pos, mut size) = (0, n);
+        for &p in &factors {
+            size /= p;
+            pos += j % p * size;
+            j /= p;
+        }
+        pos
+    };
+    let mut moved = vec![false; n];
+    for start in 0..n {
+        let (mut i, mut value) = (start, data[start]);
+        while !moved[i] {
+            moved[i] = true;
+            i = position(i);
+            value = std::mem::replace(&mut data[i], value);
+        }
+    }
+
+    // Combine `p` adjacent transforms of length `m` into one of length
+    // `len`: X[k + m*q] = Σ_r w^(r(k + m*q)) sub[r][k], where `w` is the
+    // `len`'th root of unity. The inputs for a given `k` are exactly the
+    // positions its outputs go to, so each group can be done in place.
+    let twiddles = twiddles(n, n, direction);
+    let mut m = 1;
+    for &p in factors.iter().rev() {
+        let len = m * p;
+        let mut sub = [Complex::new(0.0, 0.0); MAX_DIRECT_RADIX];
+        for block in data.chunks_exact_mut(len) {
+            for k in 0..m {
+                for (r, x) in sub[..p].iter_mut().enumerate() {
+                    *x = block[k + m * r] * twiddles[r * k * (n / len) % n];
+                }
+                for q in 0..p {
+                    block[k + m * q] = sub[..p]
+                        .iter()
+                        .enumerate()
+                        .fold(Complex::new(0.0, 0.0), |sum, (r, &x)| {
+                            sum + x * twiddles[r * q * (n / p) % n]
+                        });
+                }
+            }
+        }
+        m = len;
+    }
+}
+
+/// Transform `data` in place using Bluestein's algorithm.
+fn bluestein(data: &mut [Complex<f64>], direction: Direction) {
+    let n = data.len();
+    let m = (2 * n - 1).next_power_of_two();
+
+    // The chirp `w[k] = e^(±πik²/n)`. Reducing `k²` modulo `2n` first keeps
+    // the angle small, and so accurate.
+    let chirp: Vec<Complex<f64>> = (0..n)
+        .map(|k| {
+            let k2 = (k as u128 * k as u128 % (2 * n as u128)) as f64;
+            Complex::<f64>::from_polar(1.0, direction.sign() * PI * k2 / n as f64)
+        })
+        .collect();
+
+    let zero = Complex::new(0.0, 0.0);
+    let mut a = vec![zero; m];
+    for k in 0..n {
+        a[k] = data[k] * chirp[k];
+    }
+
+    let mut b = vec![zero; m];
+    b[0] = chirp[0].conj();
+    for k in 1..n {
+        b[k] = chirp[k].conj();
+        b[m - k] = chirp[k].conj();
+    }
+
+    // Convolve `a` with `b`, by multiplying their transforms.
+    radix2(&mut a, Direction::Forward);
+    radix2(&mut b, Direction::Forward);
+    for (x, y) in a.iter_mut().zip(&b) {
+        *x = *x * *y;
+    }
+    inverse(&mut a);
+
+    for k in 0..n {
+        data[k] = a[k] * chirp[k];
+    }
+}
+
+#[cfg(test)]
+fn naive_dft(data: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let n = data.len();
+    (0..n)
+        .map(|k| {
+            data.iter().enumerate().fold(Complex::new(0.0, 0.0), |sum, (j, &x)| {
+                let theta = -2.0 * PI * ((j * k) % n) as f64 / n as f64;
+                sum + x * Complex::<f64>::from_polar(1.0, theta)
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn sample(n: usize) -> Vec<Complex<f64>> {
+    (0..n)
+        .map(|j| {
+            let j = j as f64;
+            Complex::new((0.7 * j).sin() + 0.25 * j, (1.3 * j).cos() - 0.5)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+fn assert_close(a: &[Complex<f64>], b: &[Complex<f64>]) {
+    assert_eq!(a.len(), b.len());
+    let scale = b.iter().map(|z| z.abs()).fold(1.0, f64::max);
+    for (x, y) in a.iter().zip(b) {
+        assert!((*x - *y).abs() <= 1e-10 * scale, "{} != {}", x, y);
+    }
+}
+
+#[test]
+fn test_prime_factors() {
+    assert_eq!(prime_factors(1), vec![]);
+    assert_eq!(prime_factors(2), vec![2]);
+    assert_eq!(prime_factors(60), vec![2, 2, 3, 5]);
+    assert_eq!(prime_factors(97), vec![97]);
+    assert_eq!(prime_factors(2 * 101), vec![2, 101]);
+}
+
+#[test]
+fn test_forward_matches_naive() {
+    // Powers of two, smooth lengths, prime lengths, and lengths with a large
+    // prime factor exercise each of the three algorithms.
+    for &n in &[1, 2, 4, 16, 64, 3, 6, 12, 15, 30, 49, 60, 13, 17, 97, 202, 1000] {
+        let x = sample(n);
+        let mut y = x.clone();
+        forward(&mut y);
+        assert_close(&y, &naive_dft(&x));
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    for &n in &[8, 45, 101, 1024, 1031] {
+        let x = sample(n);
+        let mut y = x.clone();
+        forward(&mut y);
+        inverse(&mut y);
+        assert_close(&y, &x);
+    }
+}
+
+#[test]
+fn test_known_transforms() {
+    // An impulse transforms to a constant, and a constant to an impulse.
+    let mut impulse = vec![Complex::new(0.0, 0.0); 12];
+    impulse[0] = Complex::new(1.0, 0.0);
+    forward(&mut impulse);
+    assert_close(&impulse, &[Complex::new(1.0, 0.0); 12]);
+
+    let mut constant = vec![Complex::new(2.0, 0.0); 19];
+    forward(&mut constant);
+    let mut expected = vec![Complex::new(0.0, 0.0); 19];
+    expected[0] = Complex::new(38.0, 0.0);
+    assert_close(&constant, &expected);
+
+    // Parseval: the energy of the transform is `n` times that of the input.
+    let x = sample(50);
+    let mut y = x.clone();
+    forward(&mut y);
+    let energy = |v: &[Complex<f64>]| v.iter().map(|z| z.re * z.re + z.im * z.im).sum::<f64>();
+    assert!((energy(&y) - 50.0 * energy(&x)).abs() < 1e-9 * energy(&y));
+}
+
+#[test]
+fn test_real() {
+    for &n in &[1, 2, 7, 8, 15, 16, 100] {
+        let input: Vec<f64> = (0..n).map(|j| (j as f64 * 0.37).cos() * 3.0 + 1.0).collect();
+        let spectrum = forward_real(&input);
+        assert_eq!(spectrum.len(), n / 2 + 1);
+
+        let mut full: Vec<Complex<f64>> = input.iter().map(|&x| Complex::new(x, 0.0)).collect();
+        forward(&mut full);
+        assert_close(&spectrum, &full[..n / 2 + 1]);
+
+        let back = inverse_real(&spectrum, n);
+        assert_eq!(back.len(), n);
+        for (a, b) in back.iter().zip(&input) {
+            assert!((a - b).abs() < 1e-10);
+        }
+    }
+
+    assert!(forward_real(&[]).is_empty());
+    assert!(inverse_real(&[], 0).is_empty());
+}
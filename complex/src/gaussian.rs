@@ -0,0 +1,461 @@
+//! Gaussian integers: arithmetic on `Complex<i64>`.
+//!
+//! Gaussian integers support a Euclidean division, just as ordinary integers
+//! do: for any `a` and nonzero `b`, there are `q` and `r` with `a == q * b + r`
+//! and `r.norm() < b.norm()`. That's all Euclid's algorithm needs, so the
+//! `gcd` function here is the same loop as the one in the `gcd` crate.
+//!
+//! Every nonzero Gaussian integer has four associates, differing by a factor
+//! of `1`, `i`, `-1` or `-i`, and they all divide the same things. The
+//! functions here return the associate in the first quadrant: real part
+//! positive, imaginary part non-negative.
+
+use super::Complex;
+use std::convert::TryInto;
+use std::ops::{Div, Rem};
+
+impl Complex<i64> {
+    /// Return `re² + im²`, the square of the magnitude.
+    ///
+    /// The norm can be as large as `2^127`, far past what an `i64` holds, so
+    /// it's computed and returned as a `u128`.
+    pub fn norm(self) -> u128 {
+        let (re, im) = (self.re.unsigned_abs() as u128, self.im.unsigned_abs() as u128);
+        re * re + im * im
+    }
+
+    /// Return the quotient and remainder of dividing `self` by `rhs`, with
+    /// the quotient rounded to the nearest Gaussian integer. The remainder's
+    /// norm is at most half of `rhs`'s.
+    ///
+    /// Panics if `rhs` is zero. As with `i64::MIN / -1`, the quotient can
+    /// overflow when a component of either operand is `i64::MIN`.
+    pub fn div_rem(self, rhs: Complex<i64>) -> (Complex<i64>, Complex<i64>) {
+        assert!(rhs.norm() != 0, "Gaussian integer division by zero");
+        let den = rhs.norm() as i128;
+
+        // self / rhs == self * rhs.conj() / rhs.norm(). Work in i128, since
+        // the numerator's components can be as large as two norms.
+        let (a, b) = (self.re as i128, self.im as i128);
+        let (c, d) = (rhs.re as i128, rhs.im as i128);
+        let (q_re, q_im) = (round_div(a * c + b * d, den), round_div(b * c - a * d, den));
+        let q = Complex {
+            re: q_re.try_into().expect("Gaussian quotient overflows i64"),
+            im: q_im.try_into().expect("Gaussian quotient overflows i64"),
+        };
+
+        // The remainder is small, but `q * rhs` needn't fit in an i64.
+        let r = Complex {
+            re: (a - (q_re * c - q_im * d)) as i64,
+            im: (b - (q_re * d + q_im * c)) as i64,
+        };
+        (q, r)
+    }
+
+    /// Return the associate of `self` in the first quadrant, or zero.
+    ///
+    /// Panics if that associate doesn't fit in a `Complex<i64>`, which
+    /// happens only when a component is `i64::MIN`.
+    pub fn normalize(self) -> Complex<i64> {
+        let mut z = self;
+        if z.re == 0 && z.im == 0 {
+            return z;
+        }
+        // Multiply by -i, rotating clockwise, until we land in the quadrant.
+        while !(z.re > 0 && z.im >= 0) {
+            let re = z.re.checked_neg().expect("first-quadrant associate overflows i64");
+            z = Complex { re: z.im, im: re };
+        }
+        z
+    }
+
+    /// Return true if `self` is a unit: `1`, `i`, `-1` or `-i`.
+    pub fn is_unit(self) -> bool {
+        self.norm() == 1
+    }
+
+    /// Return true if `self` is a Gaussian prime: not zero or a unit, and
+    /// divisible only by units and its own associates.
+    ///
+    /// A Gaussian integer off both axes is prime exactly when its norm is an
+    /// ordinary prime. One on an axis is prime exactly when its magnitude is
+    /// an ordinary prime congruent to 3 modulo 4; other ordinary primes split,
+    /// like `5 == (2 + i) * (2 - i)`.
+    pub fn is_prime(self) -> bool {
+        if self.re == 0 || self.im == 0 {
+            let n = self.re.unsigned_abs().max(self.im.unsigned_abs());
+            n % 4 == 3 && is_prime(n as u128)
+        } else {
+            is_prime(self.norm())
+        }
+    }
+}
+
+/// Divide `n` by the positive `d`, rounding to the nearest integer, with
+/// ties rounding up.
+fn round_div(n: i128, d: i128) -> i128 {
+    let (q, r) = (n.div_euclid(d), n.rem_euclid(d));
+    // Compare 2r with d without doubling, which could overflow.
+    if r >= d - r { q + 1 } else { q }
+}
+
+/// Return `a * b % n`, without overflowing.
+fn mul_mod(a: u128, b: u128, n: u128) -> u128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product % n;
+    }
+    // Add up the shifted copies of `a` selected by the bits of `b`.
+    let add_mod = |x: u128, y: u128| if x >= n - y { x - (n - y) } else { x + y };
+    let (mut a, mut b, mut result) = (a % n, b, 0);
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a);
+        }
+        a = add_mod(a, a);
+        b >>= 1;
+    }
+    result
+}
+
+/// Return `base` raised to the `exp` power, modulo `n`.
+fn pow_mod(mut base: u128, mut exp: u128, n: u128) -> u128 {
+    let mut result = 1;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, n);
+        }
+        base = mul_mod(base, base, n);
+        exp >>= 1;
+    }
+    result
+}
+
+/// Return true if `n` is an ordinary prime.
+///
+/// This is the Baillie-PSW test: a strong probable prime test to base 2,
+/// followed by a strong Lucas test. It involves no randomness, it's been
+/// checked exhaustively to give the right answer for every `n` below `2^64`,
+/// and no composite of any size is known to pass it.
+fn is_prime(n: u128) -> bool {
+    const SMALL_PRIMES: [u128; 20] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47,
+                                      53, 59, 61, 67, 71];
+
+    if n < 2 {
+        return false;
+    }
+    for &p in &SMALL_PRIMES {
+        if n.is_multiple_of(p) {
+            return n == p;
+        }
+    }
+
+    is_strong_probable_prime(n, 2) && is_strong_lucas_probable_prime(n)
+}
+
+/// Return true if the odd number `n` passes the Miller-Rabin test to `base`.
+fn is_strong_probable_prime(n: u128, base: u128) -> bool {
+    // Write n - 1 as d * 2^s, with d odd.
+    let s = (n - 1).trailing_zeros();
+    let d = (n - 1) >> s;
+
+    let mut x = pow_mod(base, d, n);
+    if x == 1 || x == n - 1 {
+        return true;
+    }
+    for _ in 1..s {
+        x = mul_mod(x, x, n);
+        if x == n - 1 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Return true if the odd number `n`, which has no factors below 73, passes
+/// the strong Lucas test with Selfridge's parameters.
+fn is_strong_lucas_probable_prime(n: u128) -> bool {
+    // No suitable D exists for a perfect square, so the search below would
+    // never end.
+    let root = n.isqrt();
+    if root * root == n {
+        return false;
+    }
+
+    // Find the first D in 5, -7, 9, -11, ... with Jacobi symbol (D/n) == -1.
+    // Then the Lucas sequences use P = 1 and Q = (1 - D) / 4.
+    let mut d: i128 = 5;
+    loop {
+        let residue = if d > 0 { d as u128 % n } else { n - (d.unsigned_abs() % n) };
+        match jacobi(residue, n) {
+            -1 => break,
+            0 if d.unsigned_abs() != n => return false,
+            _ => {}
+        }
+        d = if d > 0 { -(d + 2) } else { -d + 2 };
+    }
+    let to_residue = |x: i128| if x >= 0 { x as u128 % n } else { n - (x.unsigned_abs() % n) };
+    let (d, q) = (to_residue(d), to_residue((1 - d) / 4));
+
+    let add = |x: u128, y: u128| if x >= n - y { x - (n - y) } else { x + y };
+    let sub = |x: u128, y: u128| if x >= y { x - y } else { x + (n - y) };
+    // Halve x modulo the odd n, without computing x + n, which could overflow.
+    let half = |x: u128| if x & 1 == 0 { x / 2 } else { x / 2 + n / 2 + 1 };
+
+    // Write n + 1 as k * 2^s, with k odd. (n is odd and below u128::MAX.)
+    let s = (n + 1).trailing_zeros();
+    let k = (n + 1) >> s;
+
+    // Walk the bits of k from the top, keeping u = U_j, v = V_j and q_j =
+    // Q^j, where j is the prefix of k seen so far, starting with j = 1.
+    let (mut u, mut v, mut q_j) = (1, 1, q);
+    for bit in (0..127 - k.leading_zeros()).rev() {
+        // Double j.
+        u = mul_mod(u, v, n);
+        v = sub(mul_mod(v, v, n), add(q_j, q_j));
+        q_j = mul_mod(q_j, q_j, n);
+        if (k >> bit) & 1 == 1 {
+            // Increment j.
+            let (u_next, v_next) = (half(add(u, v)), half(add(mul_mod(d, u, n), v)));
+            u = u_next;
+            v = v_next;
+            q_j = mul_mod(q_j, q, n);
+        }
+    }
+
+    if u == 0 || v == 0 {
+        return true;
+    }
+    for _ in 1..s {
+        v = sub(mul_mod(v, v, n), add(q_j, q_j));
+        q_j = mul_mod(q_j, q_j, n);
+        if v == 0 {
+            return true;
+        }
+    }
+    false
+}
+
+/// Return the Jacobi symbol `(a/n)`, for odd `n`.
+fn jacobi(mut a: u128, mut n: u128) -> i32 {
+    let mut result = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 { result } else { 0 }
+}
+
+/// Euclidean division, rounding the quotient to the nearest Gaussian integer.
+impl Div for Complex<i64> {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        self.div_rem(rhs).0
+    }
+}
+
+/// The remainder of Euclidean division, whose norm is at most half the
+/// divisor's.
+impl Rem for Complex<i64> {
+    type Output = Self;
+    fn rem(self, rhs: Self) -> Self {
+        self.div_rem(rhs).1
+    }
+}
+
+/// Return the greatest common divisor of `n` and `m`, normalized to the first
+/// quadrant. The result is zero only if both arguments are.
+pub fn gcd(mut n: Complex<i64>, mut m: Complex<i64>) -> Complex<i64> {
+    let zero = Complex { re: 0, im: 0 };
+    while m != zero {
+        let r = n % m;
+        n = m;
+        m = r;
+    }
+    n.normalize()
+}
+
+/// Return `(g, x, y)`, where `g` is `gcd(a, b)` and `a * x + b * y == g`.
+pub fn extended_gcd(a: Complex<i64>,
+                    b: Complex<i64>)
+    -> (Complex<i64>, Complex<i64>, Complex<i64>)
+{
+    let zero = Complex { re: 0, im: 0 };
+    let one = Complex { re: 1, im: 0 };
+
+    // Invariant: a * x0 + b * y0 == r0, and a * x1 + b * y1 == r1.
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (one, zero);
+    let (mut y0, mut y1) = (zero, one);
+    while r1 != zero {
+        let (q, r) = r0.div_rem(r1);
+        r0 = r1;
+        r1 = r;
+        let x = x0 - q * x1;
+        x0 = x1;
+        x1 = x;
+        let y = y0 - q * y1;
+        y0 = y1;
+        y1 = y;
+    }
+
+    // Rotate the result, and the coefficients with it, into the first quadrant.
+    let g = r0.normalize();
+    if r0 != g {
+        let unit = g / r0;
+        x0 = x0 * unit;
+        y0 = y0 * unit;
+    }
+    (g, x0, y0)
+}
+
+#[test]
+fn test_div_rem() {
+    let samples = [(7, 3), (-11, 4), (5, -13), (0, 9), (-8, -8), (1000003, -77)];
+    for &(a, b) in &samples {
+        for &(c, d) in &samples {
+            let (x, y) = (Complex::new(a, b), Complex::new(c, d));
+            let (q, r) = x.div_rem(y);
+            assert_eq!(q * y + r, x);
+            assert!(2 * r.norm() <= y.norm());
+            assert_eq!(x / y, q);
+            assert_eq!(x % y, r);
+        }
+    }
+
+    assert_eq!(Complex::new(5, 0).div_rem(Complex::new(2, 1)),
+               (Complex::new(2, -1), Complex::new(0, 0)));
+}
+
+#[test]
+fn test_large_components() {
+    // Norms here overflow an i64.
+    assert_eq!(Complex::new(4_000_000_000, 3).norm(), 16_000_000_000_000_000_009);
+    assert_eq!(Complex::new(i64::MIN, i64::MIN).norm(), 1 << 127);
+
+    let x = Complex::new(10, 1);
+    let y = Complex::new(4_000_000_000, 0);
+    assert_eq!(x.div_rem(y), (Complex::new(0, 0), x));
+
+    let x = Complex::new(3_000_000_000_000_000_000, -2_000_000_000_000_000_000);
+    for &y in &[Complex::new(4_000_000_007, 1), Complex::new(-3, 2), Complex::new(0, 1),
+                Complex::new(i64::MAX, i64::MAX - 1)] {
+        let (q, r) = x.div_rem(y);
+        assert_eq!(x - r, q * y);
+        assert!(2 * r.norm() <= y.norm());
+    }
+
+    assert_eq!(Complex::new(i64::MIN + 1, -5).normalize(), Complex::new(i64::MAX, 5));
+    assert!(!Complex::new(i64::MAX, 0).is_unit());
+}
+
+#[test]
+#[should_panic(expected = "overflows i64")]
+fn test_normalize_min() {
+    let _ = Complex::new(i64::MIN, 0).normalize();
+}
+
+#[test]
+#[should_panic(expected = "division by zero")]
+fn test_div_by_zero() {
+    let _ = Complex::new(1, 1) % Complex::new(0, 0);
+}
+
+#[test]
+fn test_gcd() {
+    assert_eq!(gcd(Complex::new(0, 0), Complex::new(0, 0)), Complex::new(0, 0));
+    assert_eq!(gcd(Complex::new(0, -3), Complex::new(0, 0)), Complex::new(3, 0));
+    assert_eq!(gcd(Complex::new(5, 0), Complex::new(3, 0)), Complex::new(1, 0));
+    assert_eq!(gcd(Complex::new(5, 0), Complex::new(2, 1)), Complex::new(2, 1));
+
+    // 13 and 17 are coprime norms, so the only common factor is 2 + i.
+    let g = Complex::new(2, 1);
+    let a = g * Complex::new(3, -2);
+    let b = g * Complex::new(1, 4);
+    assert_eq!(gcd(a, b), g);
+    assert_eq!(gcd(a * Complex::new(0, -1), -b), g);
+}
+
+#[test]
+fn test_extended_gcd() {
+    let pairs = [
+        (Complex::new(11, 3), Complex::new(1, 8)),
+        (Complex::new(32, 9), Complex::new(4, 11)),
+        (Complex::new(-6, 0), Complex::new(0, 4)),
+        (Complex::new(0, 0), Complex::new(-2, 3)),
+    ];
+    for &(a, b) in &pairs {
+        let (g, x, y) = extended_gcd(a, b);
+        assert_eq!(g, gcd(a, b));
+        assert_eq!(a * x + b * y, g);
+    }
+}
+
+#[test]
+fn test_is_prime() {
+    assert!(is_prime(2) && is_prime(3) && is_prime(1_000_000_007));
+    assert!(!is_prime(0) && !is_prime(1) && !is_prime(561) && !is_prime(3215031751));
+    assert!(is_prime(18_446_744_073_709_551_557));
+
+    let gaussian_primes = [(1, 1), (2, 1), (1, -2), (3, 0), (0, -7), (4, 1), (5, 2)];
+    for &(re, im) in &gaussian_primes {
+        assert!(Complex::new(re, im).is_prime(), "{} + {}i", re, im);
+    }
+
+    let composites = [(0, 0), (1, 0), (0, -1), (2, 0), (5, 0), (0, 13), (3, 3), (4, 3)];
+    for &(re, im) in &composites {
+        assert!(!Complex::new(re, im).is_prime(), "{} + {}i", re, im);
+    }
+
+    // Norms past i64 and u64.
+    assert!(Complex::new(4_000_000_000, 1).is_prime());
+    assert!(Complex::new(6_074_000_998, 3).is_prime());
+    assert!(!Complex::new(6_074_000_998, 6_074_000_998).is_prime());
+    assert!(!Complex::new(i64::MAX, i64::MAX).is_prime());
+    assert!(is_prime((1 << 89) - 1));
+    assert!(!is_prime(((1 << 61) - 1) * ((1 << 61) - 1)));
+
+    // The largest primes below 2^127 and 2^128, and products of large primes.
+    assert!(is_prime((1 << 127) - 1));
+    assert!(is_prime(u128::MAX - 158));
+    assert!(!is_prime(((1 << 89) - 1) * ((1 << 31) - 1)));
+    assert!(!is_prime(((1 << 61) - 1) * 18_446_744_073_709_551_557));
+
+    // Strong pseudoprimes to many bases at once, which fool the Miller-Rabin
+    // test with any fixed handful of small prime bases.
+    assert!(!is_prime(2047));
+    assert!(!is_prime(3_825_123_056_546_413_051));
+    assert!(!is_prime(318_665_857_834_031_151_167_461));
+    assert!(!is_prime(3_317_044_064_679_887_385_961_981));
+}
+
+#[test]
+fn test_is_prime_small() {
+    // Check against a sieve, which covers the strong Lucas pseudoprimes 5459,
+    // 5777 and 10877 among others.
+    const LIMIT: usize = 20_000;
+    let mut sieve = vec![true; LIMIT];
+    sieve[0] = false;
+    sieve[1] = false;
+    for i in 2..LIMIT {
+        if sieve[i] {
+            for j in (i * i..LIMIT).step_by(i) {
+                sieve[j] = false;
+            }
+        }
+    }
+    for (n, &prime) in sieve.iter().enumerate() {
+        assert_eq!(is_prime(n as u128), prime, "{}", n);
+    }
+    assert!(is_strong_lucas_probable_prime(5459));
+    assert!(!is_strong_lucas_probable_prime(2047));
+}
@@ -5,12 +5,39 @@
 //! isolated modules, each of which defines its own `Complex` type in its own
 //! way. The `first_cut` module is the most well-developed.
 //!
+//! The `first_cut` type is also exported as this crate's public `Complex`,
+//! and the `transcendental` module extends `Complex<f32>` and `Complex<f64>`
+//! with the usual elementary functions. The `polar` module adds polar
+//! coordinates, and the `display` and `parse` modules print and parse those
+//! types in the notations the `formatting` examples use. The `fft` module
+//! computes discrete Fourier transforms of `Complex<f64>` slices, and the
+//! `gaussian` module treats `Complex<i64>` values as Gaussian integers. The
+//! `Polynomial` type represents polynomials with complex coefficients, and
+//! `Matrix` provides dense complex matrices with LU-based solving.
+//!
 //! If you actually need a `Complex` type for real use, consider the
 //! `num_complex` crate, whose `Complex` type is incorporated into the `num`
 //! crate.
 
+pub use first_cut::Complex;
+pub use parse::{ParseComplexError, ParseComplexErrorKind};
+pub use matrix::{Lu, Matrix};
+pub use polar::Polar;
+pub use polynomial::{Polynomial, RootsError};
+
+pub mod fft;
+pub mod gaussian;
+
+mod display;
+mod matrix;
+mod parse;
+mod polar;
+mod polynomial;
+mod transcendental;
+
 macro_rules! define_complex {
     () => {
+        #[allow(dead_code)]
         #[derive(Clone, Copy, Debug)]
         struct Complex<T> {
             /// Real portion of the complex number
@@ -22,14 +49,33 @@ macro_rules! define_complex {
     };
 }
 
+// `try_it_out` forgets a `Copy` value, just as the book's example does.
+#[cfg_attr(test, allow(forgetting_copy_types))]
 mod first_cut {
     #[derive(Clone, Copy, Debug)]
-    struct Complex<T> {
+    pub struct Complex<T> {
         /// Real portion of the complex number
-        re: T,
+        pub re: T,
 
         /// Imaginary portion of the complex number
-        im: T,
+        pub im: T,
+    }
+
+    impl<T> Complex<T> {
+        pub fn new(re: T, im: T) -> Complex<T> {
+            Complex { re, im }
+        }
+
+        /// Return the complex conjugate of `self`.
+        pub fn conj(self) -> Complex<T>
+        where
+            T: std::ops::Neg<Output = T>,
+        {
+            Complex {
+                re: self.re,
+                im: -self.im,
+            }
+        }
     }
 
     use std::ops::Add;
@@ -62,6 +108,21 @@ mod first_cut {
         }
     }
 
+    use std::ops::Neg;
+
+    impl<T> Neg for Complex<T>
+    where
+        T: Neg<Output = T>,
+    {
+        type Output = Self;
+        fn neg(self) -> Self {
+            Complex {
+                re: -self.re,
+                im: -self.im,
+            }
+        }
+    }
+
     use std::ops::Mul;
 
     impl<T> Mul for Complex<T>
@@ -78,6 +139,40 @@ mod first_cut {
         }
     }
 
+    use std::ops::Div;
+
+    macro_rules! define_div {
+        ($float:ident) => {
+            impl Div for Complex<$float> {
+                type Output = Self;
+                fn div(self, rhs: Self) -> Self {
+                    // Smith's algorithm: scaling by the larger component of
+                    // the divisor keeps the intermediate products from
+                    // overflowing.
+                    let (a, b, c, d) = (self.re, self.im, rhs.re, rhs.im);
+                    if c.abs() >= d.abs() {
+                        let r = d / c;
+                        let den = c + d * r;
+                        Complex {
+                            re: (a + b * r) / den,
+                            im: (b - a * r) / den,
+                        }
+                    } else {
+                        let r = c / d;
+                        let den = c * r + d;
+                        Complex {
+                            re: (a * r + b) / den,
+                            im: (b * r - a) / den,
+                        }
+                    }
+                }
+            }
+        };
+    }
+
+    define_div!(f32);
+    define_div!(f64);
+
     #[test]
     fn try_it_out() {
         let mut z = Complex { re: 1, im: 2 };
@@ -190,6 +285,7 @@ mod impl_compound {
 }
 
 mod derive_partialeq {
+    #[allow(dead_code)]
     #[derive(Clone, Copy, Debug, PartialEq)]
     struct Complex<T> {
         re: T,
@@ -198,6 +294,7 @@ mod derive_partialeq {
 }
 
 mod derive_everything {
+    #[allow(dead_code)]
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     struct Complex<T> {
         /// Real portion of the complex number
@@ -0,0 +1,379 @@
+//! Dense matrices of `Complex<f64>` values, and basic linear algebra on them.
+
+use super::Complex;
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
+
+/// A matrix with `rows` rows and `cols` columns, stored in row-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T> Matrix<T> {
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(row < self.rows && col < self.cols,
+                "index ({}, {}) out of range for {}x{} matrix",
+                row, col, self.rows, self.cols);
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        assert!(row < self.rows && col < self.cols,
+                "index ({}, {}) out of range for {}x{} matrix",
+                row, col, self.rows, self.cols);
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+fn zero() -> Complex<f64> {
+    Complex::new(0.0, 0.0)
+}
+
+fn one() -> Complex<f64> {
+    Complex::new(1.0, 0.0)
+}
+
+/// The LU decomposition of a square matrix, with partial pivoting: the
+/// matrix's rows, permuted by `permutation`, equal `L * U`.
+///
+/// `L` is unit lower triangular and `U` upper triangular; both are packed
+/// into `lu`, with `L`'s diagonal of ones left implicit.
+#[derive(Clone, Debug)]
+pub struct Lu {
+    lu: Matrix<Complex<f64>>,
+    permutation: Vec<usize>,
+    odd_swaps: bool,
+}
+
+impl Matrix<Complex<f64>> {
+    /// Return a `rows` by `cols` matrix of zeros.
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix { rows, cols, data: vec![zero(); rows * cols] }
+    }
+
+    /// Return the `n` by `n` identity matrix.
+    pub fn identity(n: usize) -> Self {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = one();
+        }
+        m
+    }
+
+    /// Return a matrix with the given rows.
+    ///
+    /// Panics if the rows are not all the same length.
+    pub fn from_rows(rows: Vec<Vec<Complex<f64>>>) -> Self {
+        let cols = rows.first().map_or(0, |row| row.len());
+        assert!(rows.iter().all(|row| row.len() == cols),
+                "matrix rows must all have the same length");
+        Matrix { rows: rows.len(), cols, data: rows.concat() }
+    }
+
+    /// Return the conjugate transpose, also called the Hermitian adjoint.
+    pub fn conj_transpose(&self) -> Self {
+        let mut t = Matrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)].conj();
+            }
+        }
+        t
+    }
+
+    /// Return the product of `self` and the column vector `v`.
+    pub fn mul_vec(&self, v: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        assert_eq!(self.cols, v.len(), "vector length must match matrix columns");
+        (0..self.rows)
+            .map(|i| (0..self.cols).fold(zero(), |sum, j| sum + self[(i, j)] * v[j]))
+            .collect()
+    }
+
+    /// Return the LU decomposition of `self`, or `None` if `self` is
+    /// singular.
+    ///
+    /// A pivot is treated as zero if its magnitude is no more than `n * ε`
+    /// times that of the largest element in its row of `self`, where `ε` is
+    /// `f64::EPSILON`: at that point, rounding error could account for the
+    /// whole value. Scaling by the row rather than the whole matrix means a
+    /// badly scaled but nonsingular matrix, like `diag(1e20, 1)`, still
+    /// decomposes.
+    ///
+    /// Panics if `self` is not square.
+    pub fn lu(&self) -> Option<Lu> {
+        let (lu, singular) = self.factor();
+        if singular { None } else { Some(lu) }
+    }
+
+    /// Decompose `self`, returning the decomposition and whether some pivot
+    /// was too small to trust. A column with no nonzero pivot at all is left
+    /// as it is, so the decomposition's determinant is still correct.
+    fn factor(&self) -> (Lu, bool) {
+        assert!(self.is_square(), "LU decomposition requires a square matrix");
+        let n = self.rows;
+        // The tolerance for each row's pivot, moved along with the row.
+        let mut tolerances: Vec<f64> = (0..n)
+            .map(|i| {
+                let largest = (0..n).map(|j| self[(i, j)].abs()).fold(0.0, f64::max);
+                n as f64 * f64::EPSILON * largest
+            })
+            .collect();
+
+        let mut lu = self.clone();
+        let mut permutation: Vec<usize> = (0..n).collect();
+        let mut odd_swaps = false;
+        let mut singular = false;
+
+        for k in 0..n {
+            let pivot = (k..n)
+                .max_by(|&a, &b| lu[(a, k)].abs().total_cmp(&lu[(b, k)].abs()))
+                .unwrap();
+            if lu[(pivot, k)].abs() <= tolerances[pivot] {
+                singular = true;
+                if lu[(pivot, k)] == zero() {
+                    continue;
+                }
+            }
+            if pivot != k {
+                for j in 0..n {
+                    lu.data.swap(k * n + j, pivot * n + j);
+                }
+                permutation.swap(k, pivot);
+                tolerances.swap(k, pivot);
+                odd_swaps = !odd_swaps;
+            }
+
+            for i in k + 1..n {
+                let factor = lu[(i, k)] / lu[(k, k)];
+                lu[(i, k)] = factor;
+                for j in k + 1..n {
+                    lu[(i, j)] = lu[(i, j)] - factor * lu[(k, j)];
+                }
+            }
+        }
+
+        (Lu { lu, permutation, odd_swaps }, singular)
+    }
+
+    /// Solve `self * x == b` for `x`, or return `None` if `self` is singular.
+    pub fn solve(&self, b: &[Complex<f64>]) -> Option<Vec<Complex<f64>>> {
+        self.lu().map(|lu| lu.solve(b))
+    }
+
+    /// Return the determinant. This is the product of the pivots even when
+    /// `lu` would call `self` singular, so it is zero only if some pivot is.
+    pub fn determinant(&self) -> Complex<f64> {
+        self.factor().0.determinant()
+    }
+
+    /// Return the inverse, or `None` if `self` is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        self.lu().map(|lu| lu.inverse())
+    }
+}
+
+impl Lu {
+    /// Solve `A * x == b` for `x`, where `A` is the decomposed matrix.
+    pub fn solve(&self, b: &[Complex<f64>]) -> Vec<Complex<f64>> {
+        let n = self.lu.rows;
+        assert_eq!(b.len(), n, "right-hand side length must match matrix size");
+
+        // Forward substitution with L, then back substitution with U.
+        let mut x: Vec<Complex<f64>> = self.permutation.iter().map(|&i| b[i]).collect();
+        for i in 0..n {
+            for j in 0..i {
+                x[i] = x[i] - self.lu[(i, j)] * x[j];
+            }
+        }
+        for i in (0..n).rev() {
+            for j in i + 1..n {
+                x[i] = x[i] - self.lu[(i, j)] * x[j];
+            }
+            x[i] = x[i] / self.lu[(i, i)];
+        }
+        x
+    }
+
+    /// Return the determinant of the decomposed matrix.
+    pub fn determinant(&self) -> Complex<f64> {
+        let product = (0..self.lu.rows).fold(one(), |d, i| d * self.lu[(i, i)]);
+        if self.odd_swaps { -product } else { product }
+    }
+
+    /// Return the inverse of the decomposed matrix.
+    pub fn inverse(&self) -> Matrix<Complex<f64>> {
+        let n = self.lu.rows;
+        let mut inverse = Matrix::zeros(n, n);
+        let mut e = vec![zero(); n];
+        for j in 0..n {
+            e[j] = one();
+            for (i, x) in self.solve(&e).into_iter().enumerate() {
+                inverse[(i, j)] = x;
+            }
+            e[j] = zero();
+        }
+        inverse
+    }
+}
+
+impl Add for Matrix<Complex<f64>> {
+    type Output = Self;
+    fn add(mut self, rhs: Self) -> Self {
+        assert!(self.rows == rhs.rows && self.cols == rhs.cols,
+                "matrix dimensions must match");
+        for (a, b) in self.data.iter_mut().zip(rhs.data) {
+            *a = *a + b;
+        }
+        self
+    }
+}
+
+impl Sub for Matrix<Complex<f64>> {
+    type Output = Self;
+    fn sub(mut self, rhs: Self) -> Self {
+        assert!(self.rows == rhs.rows && self.cols == rhs.cols,
+                "matrix dimensions must match");
+        for (a, b) in self.data.iter_mut().zip(rhs.data) {
+            *a = *a - b;
+        }
+        self
+    }
+}
+
+impl Mul for &Matrix<Complex<f64>> {
+    type Output = Matrix<Complex<f64>>;
+    fn mul(self, rhs: Self) -> Matrix<Complex<f64>> {
+        assert_eq!(self.cols, rhs.rows,
+                   "left matrix's columns must match right matrix's rows");
+        let mut product = Matrix::zeros(self.rows, rhs.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                for j in 0..rhs.cols {
+                    product[(i, j)] = product[(i, j)] + a * rhs[(k, j)];
+                }
+            }
+        }
+        product
+    }
+}
+
+impl Mul for Matrix<Complex<f64>> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        &self * &rhs
+    }
+}
+
+#[cfg(test)]
+fn c(re: f64, im: f64) -> Complex<f64> {
+    Complex::new(re, im)
+}
+
+#[cfg(test)]
+fn assert_close(a: &Matrix<Complex<f64>>, b: &Matrix<Complex<f64>>) {
+    assert!(a.rows == b.rows && a.cols == b.cols);
+    for (x, y) in a.data.iter().zip(&b.data) {
+        assert!((*x - *y).abs() < 1e-12, "{:?} != {:?}", a, b);
+    }
+}
+
+#[test]
+fn test_quantum_gates() {
+    let s = 0.5f64.sqrt();
+    let hadamard = Matrix::from_rows(vec![vec![c(s, 0.0), c(s, 0.0)],
+                                          vec![c(s, 0.0), c(-s, 0.0)]]);
+    let pauli_y = Matrix::from_rows(vec![vec![zero(), c(0.0, -1.0)],
+                                         vec![c(0.0, 1.0), zero()]]);
+    let identity = Matrix::identity(2);
+
+    // Both gates are unitary, and their own inverses.
+    assert_close(&(&hadamard * &hadamard.conj_transpose()), &identity);
+    assert_close(&(&pauli_y * &pauli_y.conj_transpose()), &identity);
+    assert_close(&(&hadamard * &hadamard), &identity);
+    assert_eq!(pauli_y.conj_transpose(), pauli_y);
+    assert_close(&pauli_y.inverse().unwrap(), &pauli_y);
+
+    // Applied to |0⟩, the Hadamard gate produces an even superposition.
+    assert_eq!(hadamard.mul_vec(&[one(), zero()]), vec![c(s, 0.0), c(s, 0.0)]);
+    assert!((pauli_y.determinant() - c(-1.0, 0.0)).abs() < 1e-15);
+}
+
+#[test]
+fn test_solve() {
+    // Mesh analysis of a two-loop AC circuit: Z * I = V.
+    let z = Matrix::from_rows(vec![vec![c(10.0, 5.0), c(-4.0, 0.0)],
+                                   vec![c(-4.0, 0.0), c(8.0, -3.0)]]);
+    let v = [c(12.0, 0.0), c(0.0, 0.0)];
+    let current = z.solve(&v).unwrap();
+    let check = z.mul_vec(&current);
+    for (a, b) in check.iter().zip(&v) {
+        assert!((*a - *b).abs() < 1e-12);
+    }
+
+    // A matrix that needs pivoting: its top-left element is zero.
+    let m = Matrix::from_rows(vec![vec![zero(), c(2.0, 1.0), one()],
+                                   vec![c(1.0, -1.0), c(3.0, 0.0), c(0.0, 2.0)],
+                                   vec![c(4.0, 0.0), one(), c(-1.0, 1.0)]]);
+    let x = [c(1.0, 2.0), c(-3.0, 0.5), c(0.0, -1.0)];
+    let b = m.mul_vec(&x);
+    for (a, b) in m.solve(&b).unwrap().iter().zip(&x) {
+        assert!((*a - *b).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_determinant_and_inverse() {
+    let m = Matrix::from_rows(vec![vec![c(1.0, 1.0), c(2.0, 0.0)],
+                                   vec![c(0.0, 3.0), c(4.0, -1.0)]]);
+    // (1 + i)(4 - i) - 2(3i) == 5 - 3i
+    assert!((m.determinant() - c(5.0, -3.0)).abs() < 1e-12);
+
+    let inverse = m.inverse().unwrap();
+    assert_close(&(&m * &inverse), &Matrix::identity(2));
+    assert_close(&(&inverse * &m), &Matrix::identity(2));
+
+    // The third row is the sum of the first two.
+    let singular = Matrix::from_rows(vec![vec![one(), c(0.0, 1.0), c(2.0, 0.0)],
+                                          vec![c(3.0, 0.0), one(), c(0.0, -1.0)],
+                                          vec![c(4.0, 0.0), c(1.0, 1.0), c(2.0, -1.0)]]);
+    assert!(singular.lu().is_none());
+    assert!(singular.inverse().is_none());
+    assert!(singular.solve(&[one(), one(), one()]).is_none());
+    assert_eq!(singular.determinant(), zero());
+
+    assert_eq!(Matrix::identity(3).determinant(), one());
+
+    // Badly scaled, but far from singular.
+    let scaled = Matrix::from_rows(vec![vec![c(1e20, 0.0), zero()], vec![zero(), one()]]);
+    assert!(scaled.lu().is_some());
+    assert_eq!(scaled.determinant(), c(1e20, 0.0));
+    assert_close(&(&scaled * &scaled.inverse().unwrap()), &Matrix::identity(2));
+
+    // A zero column makes some pivot exactly zero.
+    let zero_column = Matrix::from_rows(vec![vec![zero(), one()], vec![zero(), c(2.0, 0.0)]]);
+    assert!(zero_column.lu().is_none());
+    assert_eq!(zero_column.determinant(), zero());
+
+    assert_close(&(m.clone() - m.clone()), &Matrix::zeros(2, 2));
+    assert_eq!((m.clone() + m)[(1, 0)], c(0.0, 6.0));
+}
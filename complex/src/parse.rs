@@ -0,0 +1,239 @@
+//! Parsing `Complex<f32>` and `Complex<f64>` values from strings.
+//!
+//! Two notations are accepted, matching the two ways the `formatting`
+//! examples display complex numbers:
+//!
+//! - Rectangular: `3+4i`, `-0.5 - 0.866i`, `-2i`, `i`, `5`. Whitespace is
+//!   allowed around the sign joining the two parts, but not inside either
+//!   number.
+//! - Polar: `2 ∠ 90°`. The angle is in degrees when followed by `°`, and in
+//!   radians otherwise.
+
+use super::Complex;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The reason a string couldn't be parsed as a `Complex`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseComplexErrorKind {
+    /// The string was empty, or held only whitespace.
+    Empty,
+    /// The real part of a rectangular number wasn't a valid number.
+    Real,
+    /// The imaginary part of a rectangular number wasn't a valid number.
+    Imaginary,
+    /// The magnitude of a polar number wasn't a valid, non-negative number.
+    Magnitude,
+    /// The angle of a polar number wasn't a valid number.
+    Angle,
+}
+
+/// An error returned when parsing a `Complex` fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseComplexError {
+    kind: ParseComplexErrorKind,
+    text: String,
+}
+
+impl ParseComplexError {
+    fn new(kind: ParseComplexErrorKind, text: &str) -> ParseComplexError {
+        ParseComplexError {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    /// Return what went wrong.
+    pub fn kind(&self) -> ParseComplexErrorKind {
+        self.kind
+    }
+
+    /// Return the portion of the input that couldn't be parsed.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for ParseComplexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ParseComplexErrorKind::*;
+        match self.kind {
+            Empty => write!(f, "cannot parse complex number from empty string"),
+            Real => write!(f, "invalid real part in complex number: {:?}", self.text),
+            Imaginary => write!(f, "invalid imaginary part in complex number: {:?}", self.text),
+            Magnitude => write!(f, "invalid magnitude in polar complex number: {:?}", self.text),
+            Angle => write!(f, "invalid angle in polar complex number: {:?}", self.text),
+        }
+    }
+}
+
+impl Error for ParseComplexError {}
+
+/// Split the rectangular notation `s` into its real and imaginary parts.
+/// Either may be absent; a present imaginary part has had its `i` removed.
+fn split_rectangular(s: &str) -> (Option<&str>, Option<String>) {
+    let body = match s.strip_suffix('i') {
+        Some(body) => body,
+        None => return (Some(s), None),
+    };
+
+    // Find the sign that separates the real and imaginary parts: the first
+    // `+` or `-` that isn't the leading sign, and isn't an exponent's sign.
+    let split = body.char_indices().find(|&(pos, ch)| {
+        (ch == '+' || ch == '-')
+            && pos > 0
+            && !body[..pos].trim_end().ends_with(['e', 'E'])
+    });
+
+    let (re, sign, coefficient) = match split {
+        Some((pos, sign)) => {
+            (Some(body[..pos].trim_end()), Some(sign), body[pos + 1..].trim_start())
+        }
+        None => match body.strip_prefix(['+', '-']) {
+            Some(rest) => (None, body.chars().next(), rest),
+            None => (None, None, body),
+        },
+    };
+
+    // A bare `i` has an implied coefficient of one.
+    let coefficient = if coefficient.is_empty() { "1" } else { coefficient };
+    let im = match sign {
+        Some(sign) => format!("{}{}", sign, coefficient),
+        None => coefficient.to_string(),
+    };
+    (re, Some(im))
+}
+
+macro_rules! define_from_str {
+    ($float:ident) => {
+        impl FromStr for Complex<$float> {
+            type Err = ParseComplexError;
+
+            fn from_str(s: &str) -> Result<Self, ParseComplexError> {
+                use ParseComplexErrorKind::*;
+
+                let s = s.trim();
+                if s.is_empty() {
+                    return Err(ParseComplexError::new(Empty, s));
+                }
+
+                if let Some((magnitude, angle)) = s.split_once('∠') {
+                    let (magnitude, angle) = (magnitude.trim(), angle.trim());
+                    let r = match magnitude.parse::<$float>() {
+                        Ok(r) if r >= 0.0 => r,
+                        _ => return Err(ParseComplexError::new(Magnitude, magnitude)),
+                    };
+                    let theta = match angle.strip_suffix('°') {
+                        Some(degrees) => degrees
+                            .trim_end()
+                            .parse::<$float>()
+                            .map(|d| Self::from_degrees(r, d)),
+                        None => angle
+                            .parse::<$float>()
+                            .map(|t| Self::from_polar(r, t)),
+                    };
+                    return theta.map_err(|_| ParseComplexError::new(Angle, angle));
+                }
+
+                let (re, im) = split_rectangular(s);
+                let re = match re {
+                    Some(re) => re
+                        .parse::<$float>()
+                        .map_err(|_| ParseComplexError::new(Real, re))?,
+                    None => 0.0,
+                };
+                let im = match im {
+                    Some(im) => im
+                        .parse::<$float>()
+                        .map_err(|_| ParseComplexError::new(Imaginary, &im))?,
+                    None => 0.0,
+                };
+                Ok(Complex::new(re, im))
+            }
+        }
+
+        impl Complex<$float> {
+            /// Return the complex number with magnitude `r` at an angle of
+            /// `degrees`. Multiples of 90° produce exact results, so that
+            /// `2 ∠ 90°` parses as exactly `2i`.
+            fn from_degrees(r: $float, degrees: $float) -> Self {
+                let quarter_turns = degrees / 90.0;
+                if quarter_turns.fract() == 0.0 {
+                    match quarter_turns.rem_euclid(4.0) as u8 {
+                        0 => Complex::new(r, 0.0),
+                        1 => Complex::new(0.0, r),
+                        2 => Complex::new(-r, 0.0),
+                        _ => Complex::new(0.0, -r),
+                    }
+                } else {
+                    Self::from_polar(r, degrees.to_radians())
+                }
+            }
+        }
+    };
+}
+
+define_from_str!(f32);
+define_from_str!(f64);
+
+#[test]
+fn test_rectangular() {
+    let parse = |s: &str| s.parse::<Complex<f64>>().unwrap();
+
+    assert_eq!(parse("3+4i"), Complex::new(3.0, 4.0));
+    assert_eq!(parse("3-4i"), Complex::new(3.0, -4.0));
+    assert_eq!(parse("-0.5 + 0.866i"), Complex::new(-0.5, 0.866));
+    assert_eq!(parse("-0.5 - 0.866i"), Complex::new(-0.5, -0.866));
+    assert_eq!(parse("  1e3+2.5e-1i "), Complex::new(1000.0, 0.25));
+    assert_eq!(parse("1E+2-1e-2i"), Complex::new(100.0, -0.01));
+    assert_eq!(parse("-2i"), Complex::new(0.0, -2.0));
+    assert_eq!(parse("+2i"), Complex::new(0.0, 2.0));
+    assert_eq!(parse("i"), Complex::new(0.0, 1.0));
+    assert_eq!(parse("-i"), Complex::new(0.0, -1.0));
+    assert_eq!(parse("7 - i"), Complex::new(7.0, -1.0));
+    assert_eq!(parse("5"), Complex::new(5.0, 0.0));
+    assert_eq!(parse("-5"), Complex::new(-5.0, 0.0));
+    assert_eq!(parse("0 + 2i"), Complex::new(0.0, 2.0));
+
+    assert_eq!("3+4i".parse::<Complex<f32>>(), Ok(Complex::new(3.0f32, 4.0)));
+}
+
+#[test]
+fn test_polar() {
+    let parse = |s: &str| s.parse::<Complex<f64>>().unwrap();
+
+    assert_eq!(parse("2 ∠ 90°"), Complex::new(0.0, 2.0));
+    assert_eq!(parse("2∠-90°"), Complex::new(0.0, -2.0));
+    assert_eq!(parse("3 ∠ 180°"), Complex::new(-3.0, 0.0));
+    assert_eq!(parse("3 ∠ 0"), Complex::new(3.0, 0.0));
+
+    let z = parse("1 ∠ 120°");
+    assert!((z.re - -0.5).abs() < 1e-15);
+    assert!((z.im - 0.75f64.sqrt()).abs() < 1e-15);
+
+    let z = parse("2 ∠ 3.141592653589793");
+    assert!((z.re - -2.0).abs() < 1e-15 && z.im.abs() < 1e-15);
+}
+
+#[test]
+fn test_errors() {
+    use ParseComplexErrorKind::*;
+
+    let kind = |s: &str| s.parse::<Complex<f64>>().unwrap_err().kind();
+
+    assert_eq!(kind(""), Empty);
+    assert_eq!(kind("   "), Empty);
+    assert_eq!(kind("abc"), Real);
+    assert_eq!(kind("3 x + 4i"), Real);
+    assert_eq!(kind("3+-4i"), Imaginary);
+    assert_eq!(kind("3+4 i"), Imaginary);
+    assert_eq!(kind("3+4j"), Real);
+    assert_eq!(kind("x ∠ 90°"), Magnitude);
+    assert_eq!(kind("-1 ∠ 90°"), Magnitude);
+    assert_eq!(kind("1 ∠ right°"), Angle);
+
+    let err = "3+-4i".parse::<Complex<f64>>().unwrap_err();
+    assert_eq!(err.text(), "+-4");
+    assert_eq!(err.to_string(), "invalid imaginary part in complex number: \"+-4\"");
+}
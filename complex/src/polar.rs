@@ -0,0 +1,96 @@
+//! Polar coordinates for `Complex<f32>` and `Complex<f64>`.
+
+use super::Complex;
+
+/// A complex number represented by its magnitude and angle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Polar<T> {
+    /// Distance from the origin; never negative for values produced by
+    /// `Complex::to_polar`.
+    pub r: T,
+
+    /// Angle counterclockwise from the positive real axis, in radians.
+    /// `Complex::to_polar` produces angles in `[-π, π]`, as `Complex::arg`
+    /// does.
+    pub theta: T,
+}
+
+impl<T> Polar<T> {
+    pub fn new(r: T, theta: T) -> Polar<T> {
+        Polar { r, theta }
+    }
+}
+
+macro_rules! define_polar {
+    ($float:ident) => {
+        impl Complex<$float> {
+            /// Return the complex number with magnitude `r` and angle
+            /// `theta`, in radians.
+            pub fn from_polar(r: $float, theta: $float) -> Self {
+                Complex {
+                    re: r * theta.cos(),
+                    im: r * theta.sin(),
+                }
+            }
+
+            /// Return the magnitude of `self`, taking care not to overflow or
+            /// underflow in squaring the components.
+            pub fn abs(self) -> $float {
+                self.re.hypot(self.im)
+            }
+
+            /// Return the angle of `self` in radians, in the range `[-π, π]`.
+            /// On the negative real axis, the sign of the zero imaginary part
+            /// picks the end: `-1 + 0i` has angle `π`, and `-1 - 0i` has `-π`.
+            pub fn arg(self) -> $float {
+                self.im.atan2(self.re)
+            }
+
+            pub fn to_polar(self) -> Polar<$float> {
+                Polar {
+                    r: self.abs(),
+                    theta: self.arg(),
+                }
+            }
+        }
+
+        impl From<Polar<$float>> for Complex<$float> {
+            fn from(p: Polar<$float>) -> Self {
+                Complex::<$float>::from_polar(p.r, p.theta)
+            }
+        }
+
+        impl From<Complex<$float>> for Polar<$float> {
+            fn from(z: Complex<$float>) -> Self {
+                z.to_polar()
+            }
+        }
+    };
+}
+
+define_polar!(f32);
+define_polar!(f64);
+
+#[test]
+fn test_polar() {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    let z = Complex::new(3.0_f64, 4.0);
+    assert_eq!(z.abs(), 5.0);
+    assert_eq!(Complex::new(0.0_f64, 2.0).arg(), FRAC_PI_2);
+    assert_eq!(Complex::new(-1.0_f64, 0.0).arg(), PI);
+    assert_eq!(Complex::new(-1.0_f64, -0.0).arg(), -PI);
+
+    let p = z.to_polar();
+    assert_eq!(p, Polar::new(5.0, 4.0_f64.atan2(3.0)));
+    let back = Complex::from(p);
+    assert!((back.re - 3.0).abs() < 1e-15 && (back.im - 4.0).abs() < 1e-15);
+    assert_eq!(Polar::from(z), p);
+
+    // Magnitudes don't overflow just because their squares would.
+    let big = Complex::new(3e200_f64, 4e200).abs();
+    assert!((big - 5e200).abs() < 1e186);
+
+    let w = Complex::<f32>::from_polar(2.0, std::f32::consts::PI);
+    assert!((w.re + 2.0).abs() < 1e-6 && w.im.abs() < 1e-6);
+}
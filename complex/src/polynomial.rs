@@ -0,0 +1,434 @@
+//! Polynomials with `Complex<f64>` coefficients.
+
+use super::{fft, Complex};
+use std::error::Error;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+
+/// Products of polynomials whose shorter factor has more coefficients than
+/// this are computed with the FFT, rather than term by term.
+const FFT_MULTIPLY_THRESHOLD: usize = 32;
+
+/// The most iterations `roots` will spend before giving up on convergence.
+const MAX_ROOT_ITERATIONS: usize = 500;
+
+/// The most Newton steps `roots` will take to polish a multiple root.
+const MAX_POLISH_ITERATIONS: usize = 50;
+
+/// A polynomial `c[0] + c[1] z + c[2] z² + ...`.
+///
+/// Coefficients are stored lowest degree first, with no trailing zeros, so
+/// the zero polynomial has no coefficients at all.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial<T> {
+    coefficients: Vec<T>,
+}
+
+fn zero() -> Complex<f64> {
+    Complex::new(0.0, 0.0)
+}
+
+/// An error returned when `Polynomial::roots` doesn't converge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RootsError {
+    estimates: Vec<Complex<f64>>,
+}
+
+impl RootsError {
+    /// Return the approximations to the roots at the point `roots` gave up.
+    pub fn estimates(&self) -> &[Complex<f64>] {
+        &self.estimates
+    }
+}
+
+impl fmt::Display for RootsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "polynomial roots did not converge in {} iterations", MAX_ROOT_ITERATIONS)
+    }
+}
+
+impl Error for RootsError {}
+
+impl Polynomial<Complex<f64>> {
+    /// Return the polynomial with the given coefficients, lowest degree first.
+    pub fn new(mut coefficients: Vec<Complex<f64>>) -> Self {
+        while coefficients.last() == Some(&zero()) {
+            coefficients.pop();
+        }
+        Polynomial { coefficients }
+    }
+
+    /// Return the monic polynomial whose roots are `roots`, with repetition.
+    pub fn from_roots(roots: &[Complex<f64>]) -> Self {
+        roots.iter().fold(Polynomial::new(vec![Complex::new(1.0, 0.0)]), |p, &r| {
+            p * Polynomial::new(vec![-r, Complex::new(1.0, 0.0)])
+        })
+    }
+
+    /// Return the coefficients, lowest degree first.
+    pub fn coefficients(&self) -> &[Complex<f64>] {
+        &self.coefficients
+    }
+
+    /// Return the degree, or `None` for the zero polynomial.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    /// Evaluate the polynomial at `z`, using Horner's rule.
+    pub fn eval(&self, z: Complex<f64>) -> Complex<f64> {
+        self.coefficients.iter().rev().fold(zero(), |acc, &c| acc * z + c)
+    }
+
+    /// Return the derivative.
+    pub fn derivative(&self) -> Self {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(k, c)| Complex::new(c.re * k as f64, c.im * k as f64))
+            .collect();
+        Polynomial::new(coefficients)
+    }
+
+    /// Return a bound on the rounding error `eval` could make at `z`: a
+    /// small multiple of `ε Σ |c[k]| |z|ᵏ`. Values smaller than this are
+    /// indistinguishable from zero.
+    fn eval_error(&self, z: Complex<f64>) -> f64 {
+        let size = self.coefficients.iter().rev().fold(0.0, |acc, c| acc * z.abs() + c.abs());
+        4.0 * self.coefficients.len() as f64 * f64::EPSILON * size
+    }
+
+    /// Return all the roots, with repetition, found by the Aberth-Ehrlich
+    /// method. The zero polynomial and nonzero constants have no roots.
+    ///
+    /// A root of multiplicity `m` is ill-conditioned: the iteration can only
+    /// find it to about the `m`'th root of the precision. So once the
+    /// iteration settles, approximations clustered around a common root are
+    /// replaced by that root, found by Newton's method on the `m - 1`'th
+    /// derivative, in which it is a simple root.
+    ///
+    /// Return an error holding the current approximations if the iteration
+    /// hasn't converged after `MAX_ROOT_ITERATIONS` steps.
+    pub fn roots(&self) -> Result<Vec<Complex<f64>>, RootsError> {
+        // Factor out any power of z, whose roots are exactly zero.
+        let zeros = self.coefficients.iter().take_while(|&&c| c == zero()).count();
+        let p = Polynomial::new(self.coefficients[zeros..].to_vec());
+        let mut roots = vec![zero(); zeros];
+
+        let n = match p.degree() {
+            None | Some(0) => return Ok(roots),
+            Some(1) => {
+                roots.push(-p.coefficients[0] / p.coefficients[1]);
+                return Ok(roots);
+            }
+            Some(n) => n,
+        };
+
+        // Start with guesses spread around a circle that contains all the
+        // roots, offset from the axes so as not to start in a symmetric spot.
+        let lead = p.coefficients[n].abs();
+        let radius = 1.0 + p.coefficients[..n]
+            .iter()
+            .map(|c| c.abs() / lead)
+            .fold(0.0, f64::max);
+        let mut z: Vec<Complex<f64>> = (0..n)
+            .map(|k| {
+                let theta = 2.0 * std::f64::consts::PI * k as f64 / n as f64 + 0.4;
+                Complex::<f64>::from_polar(radius, theta)
+            })
+            .collect();
+
+        let dp = p.derivative();
+        let one = Complex::new(1.0, 0.0);
+        let mut converged = false;
+        for _ in 0..MAX_ROOT_ITERATIONS {
+            converged = true;
+            for k in 0..n {
+                // Once the value is lost in rounding error, further steps
+                // would only wander.
+                let value = p.eval(z[k]);
+                if value.abs() <= p.eval_error(z[k]) {
+                    continue;
+                }
+                let ratio = value / dp.eval(z[k]);
+                let repulsion = (0..n)
+                    .filter(|&j| j != k)
+                    .fold(zero(), |sum, j| sum + one / (z[k] - z[j]));
+                let offset = ratio / (one - ratio * repulsion);
+                if offset.re.is_finite() && offset.im.is_finite() {
+                    z[k] = z[k] - offset;
+                    if offset.abs() > 1e-14 * z[k].abs().max(1.0) {
+                        converged = false;
+                    }
+                } else {
+                    converged = false;
+                }
+            }
+            if converged {
+                break;
+            }
+        }
+        if !converged {
+            roots.extend(z);
+            return Err(RootsError { estimates: roots });
+        }
+
+        p.polish_multiple_roots(&mut z);
+        roots.extend(z);
+        Ok(roots)
+    }
+
+    /// Given approximations `z` to all the roots of `self`, find clusters of
+    /// them that surround a multiple root, and replace each with the root.
+    fn polish_multiple_roots(&self, z: &mut [Complex<f64>]) {
+        let n = z.len();
+        let dp = self.derivative();
+
+        // The disk of radius `n |p(z)/p'(z)|` around `z` contains a root of
+        // `p`, so approximations whose disks overlap may share a root.
+        let radii: Vec<f64> = z
+            .iter()
+            .map(|&x| {
+                let r = n as f64 * (self.eval(x) / dp.eval(x)).abs();
+                if r.is_finite() { r } else { 0.0 }
+            })
+            .collect();
+        let mut cluster: Vec<usize> = (0..n).collect();
+        for i in 0..n {
+            for j in i + 1..n {
+                if cluster[i] != cluster[j] && (z[i] - z[j]).abs() <= radii[i] + radii[j] {
+                    let (old, new) = (cluster[j], cluster[i]);
+                    for c in cluster.iter_mut().filter(|c| **c == old) {
+                        *c = new;
+                    }
+                }
+            }
+        }
+
+        for id in 0..n {
+            let members: Vec<usize> = (0..n).filter(|&i| cluster[i] == id).collect();
+            let m = members.len();
+            if m < 2 {
+                continue;
+            }
+            let sum = members.iter().fold(zero(), |sum, &i| sum + z[i]);
+            let center = Complex::new(sum.re / m as f64, sum.im / m as f64);
+            let reach = members
+                .iter()
+                .map(|&i| (z[i] - center).abs() + radii[i])
+                .fold(0.0, f64::max);
+
+            // The root is a simple root of the `m - 1`'th derivative.
+            let q = (1..m).fold(self.clone(), |q, _| q.derivative());
+            let dq = q.derivative();
+            let mut root = center;
+            for _ in 0..MAX_POLISH_ITERATIONS {
+                let step = q.eval(root) / dq.eval(root);
+                if !(step.re.is_finite() && step.im.is_finite()) {
+                    break;
+                }
+                root = root - step;
+                if step.abs() <= f64::EPSILON * root.abs() {
+                    break;
+                }
+            }
+
+            // Keep the polished root only if it stays within the cluster,
+            // and `p` and its derivatives below the `m - 1`'th all vanish
+            // there, to within rounding error.
+            let mut d = self.clone();
+            let mut multiple = (root - center).abs() <= reach;
+            for _ in 1..m {
+                multiple &= d.eval(root).abs() <= d.eval_error(root);
+                d = d.derivative();
+            }
+            if multiple {
+                for &i in &members {
+                    z[i] = root;
+                }
+            }
+        }
+    }
+}
+
+impl Add for Polynomial<Complex<f64>> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        let (mut long, short) = if self.coefficients.len() >= rhs.coefficients.len() {
+            (self.coefficients, rhs.coefficients)
+        } else {
+            (rhs.coefficients, self.coefficients)
+        };
+        for (a, b) in long.iter_mut().zip(short) {
+            *a = *a + b;
+        }
+        Polynomial::new(long)
+    }
+}
+
+impl Sub for Polynomial<Complex<f64>> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        let mut difference = self.coefficients;
+        if difference.len() < rhs.coefficients.len() {
+            difference.resize(rhs.coefficients.len(), zero());
+        }
+        for (a, b) in difference.iter_mut().zip(rhs.coefficients) {
+            *a = *a - b;
+        }
+        Polynomial::new(difference)
+    }
+}
+
+impl Mul for Polynomial<Complex<f64>> {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.coefficients, &rhs.coefficients);
+        if a.is_empty() || b.is_empty() {
+            return Polynomial::new(vec![]);
+        }
+
+        let len = a.len() + b.len() - 1;
+        if a.len().min(b.len()) <= FFT_MULTIPLY_THRESHOLD {
+            let mut product = vec![zero(); len];
+            for (i, &x) in a.iter().enumerate() {
+                for (j, &y) in b.iter().enumerate() {
+                    product[i + j] = product[i + j] + x * y;
+                }
+            }
+            return Polynomial::new(product);
+        }
+
+        // Multiplying the transforms of the coefficient sequences convolves
+        // the sequences themselves. Pad to a power of two, long enough that
+        // the convolution doesn't wrap around.
+        let size = len.next_power_of_two();
+        let mut fa = a.clone();
+        let mut fb = b.clone();
+        fa.resize(size, zero());
+        fb.resize(size, zero());
+        fft::forward(&mut fa);
+        fft::forward(&mut fb);
+        for (x, y) in fa.iter_mut().zip(&fb) {
+            *x = *x * *y;
+        }
+        fft::inverse(&mut fa);
+        fa.truncate(len);
+        Polynomial::new(fa)
+    }
+}
+
+#[cfg(test)]
+fn c(re: f64, im: f64) -> Complex<f64> {
+    Complex::new(re, im)
+}
+
+/// Assert that `roots` matches `expected` in some order.
+#[cfg(test)]
+fn assert_roots(mut roots: Vec<Complex<f64>>, expected: &[Complex<f64>], tolerance: f64) {
+    assert_eq!(roots.len(), expected.len());
+    for e in expected {
+        let (index, distance) = roots
+            .iter()
+            .map(|r| (*r - *e).abs())
+            .enumerate()
+            .fold((0, f64::INFINITY), |best, (i, d)| if d < best.1 { (i, d) } else { best });
+        assert!(distance < tolerance, "no root near {}: {:?}", e, roots);
+        roots.remove(index);
+    }
+}
+
+#[test]
+fn test_basics() {
+    // 1 + 2z + 3z², with trailing zeros trimmed.
+    let p = Polynomial::new(vec![c(1.0, 0.0), c(2.0, 0.0), c(3.0, 0.0), zero()]);
+    assert_eq!(p.degree(), Some(2));
+    assert_eq!(p.eval(c(2.0, 0.0)), c(17.0, 0.0));
+    assert_eq!(p.eval(c(0.0, 1.0)), c(-2.0, 2.0));
+    assert_eq!(p.derivative(), Polynomial::new(vec![c(2.0, 0.0), c(6.0, 0.0)]));
+    assert_eq!(Polynomial::new(vec![zero()]).degree(), None);
+
+    let q = Polynomial::new(vec![c(0.0, 1.0), c(-2.0, 0.0), c(-3.0, 0.0)]);
+    assert_eq!(p.clone() + q.clone(), Polynomial::new(vec![c(1.0, 1.0)]));
+    assert_eq!(p.clone() - p.clone(), Polynomial::new(vec![]));
+    assert_eq!(q.clone() - p.clone(),
+               Polynomial::new(vec![c(-1.0, 1.0), c(-4.0, 0.0), c(-6.0, 0.0)]));
+    assert_eq!(Polynomial::from_roots(&[c(1.0, 0.0), c(-1.0, 0.0)]),
+               Polynomial::new(vec![c(-1.0, 0.0), zero(), c(1.0, 0.0)]));
+}
+
+#[test]
+fn test_fft_multiply() {
+    let coefficients = |n: usize, seed: f64| -> Vec<Complex<f64>> {
+        (0..n).map(|k| c((k as f64 * seed).sin(), (k as f64 * seed * 0.7).cos())).collect()
+    };
+    let a = coefficients(100, 0.3);
+    let b = coefficients(70, 1.1);
+
+    let mut naive = vec![zero(); 169];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            naive[i + j] = naive[i + j] + x * y;
+        }
+    }
+
+    let product = Polynomial::new(a) * Polynomial::new(b);
+    assert_eq!(product.degree(), Some(168));
+    for (x, y) in product.coefficients().iter().zip(&naive) {
+        assert!((*x - *y).abs() < 1e-10);
+    }
+}
+
+#[test]
+fn test_roots() {
+    let expected = [c(1.0, 0.0), c(-2.0, 0.0), c(0.0, 1.0), c(0.0, -1.0), c(0.5, 3.0)];
+    assert_roots(Polynomial::from_roots(&expected).roots().unwrap(), &expected, 1e-10);
+
+    // The roots of z⁸ - 1 are the eighth roots of unity.
+    let mut coefficients = vec![zero(); 9];
+    coefficients[0] = c(-1.0, 0.0);
+    coefficients[8] = c(1.0, 0.0);
+    let unity: Vec<Complex<f64>> = (0..8)
+        .map(|k| Complex::<f64>::from_polar(1.0, std::f64::consts::PI * k as f64 / 4.0))
+        .collect();
+    assert_roots(Polynomial::new(coefficients).roots().unwrap(), &unity, 1e-12);
+
+    // Zero roots come out exactly; constants have none.
+    let p = Polynomial::new(vec![zero(), zero(), c(3.0, 0.0), c(1.0, 0.0)]);
+    assert_roots(p.roots().unwrap(), &[zero(), zero(), c(-3.0, 0.0)], 1e-12);
+    assert!(Polynomial::new(vec![c(4.0, 0.0)]).roots().unwrap().is_empty());
+
+    // A larger polynomial: every root found really is a root.
+    let p = Polynomial::new((0..25).map(|k| c(1.0 + k as f64, (k as f64).cos())).collect());
+    let roots = p.roots().unwrap();
+    assert_eq!(roots.len(), 24);
+    for r in roots {
+        assert!(p.eval(r).abs() < 1e-8 * p.derivative().eval(r).abs().max(1.0));
+    }
+}
+
+#[test]
+fn test_multiple_roots() {
+    // Without polishing, a triple root is only found to about ε^(1/3).
+    let cube = Polynomial::from_roots(&[c(1.0, 0.0); 3]);
+    assert_roots(cube.roots().unwrap(), &[c(1.0, 0.0); 3], 1e-12);
+
+    let expected = [c(2.0, 0.0), c(2.0, 0.0), c(2.0, 0.0), c(2.0, 0.0),
+                    c(0.0, 1.0), c(0.0, 1.0), c(-3.0, 0.5)];
+    assert_roots(Polynomial::from_roots(&expected).roots().unwrap(), &expected, 1e-9);
+
+    // Distinct but close roots are ill-conditioned too; they come out as
+    // accurately as the coefficients allow.
+    let close = [c(1.0, 0.0), c(1.0 + 1e-6, 0.0), c(-1.0, 0.0)];
+    assert_roots(Polynomial::from_roots(&close).roots().unwrap(), &close, 1e-8);
+}
+
+#[test]
+fn test_roots_failure() {
+    let p = Polynomial::new(vec![c(f64::NAN, 0.0), c(1.0, 0.0), c(1.0, 0.0)]);
+    let error = p.roots().unwrap_err();
+    assert_eq!(error.estimates().len(), 2);
+    assert_eq!(error.to_string(), "polynomial roots did not converge in 500 iterations");
+}
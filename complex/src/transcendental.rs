@@ -0,0 +1,412 @@
+//! Elementary functions on `Complex<f32>` and `Complex<f64>`.
+//!
+//! Multivalued functions return their principal values, with the branch cuts
+//! placed where C99 and most numeric libraries put them:
+//!
+//! - `ln`, `sqrt`, `powc` and `powf` are cut along the negative real axis.
+//! - `asin` and `acos` are cut along the real axis outside `[-1, 1]`.
+//! - `atan` is cut along the imaginary axis outside `[-i, i]`.
+//! - `asinh` is cut along the imaginary axis outside `[-i, i]`.
+//! - `acosh` is cut along the real axis to the left of `1`.
+//! - `atanh` is cut along the real axis outside `[-1, 1]`.
+//!
+//! On a cut, the sign of the zero component picks the side: `(-4.0, 0.0)` has
+//! a square root of `2i`, but `(-4.0, -0.0)` has a square root of `-2i`.
+
+use super::{Complex, Polar};
+
+macro_rules! define_transcendental {
+    ($float:ident) => {
+        impl Complex<$float> {
+            fn real(re: $float) -> Self {
+                Complex { re, im: 0.0 }
+            }
+
+            fn scale(self, k: $float) -> Self {
+                Complex {
+                    re: self.re * k,
+                    im: self.im * k,
+                }
+            }
+
+            fn is_zero(self) -> bool {
+                self.re == 0.0 && self.im == 0.0
+            }
+
+            /// Return `e` raised to the power `self`.
+            pub fn exp(self) -> Self {
+                Self::from_polar(self.re.exp(), self.im)
+            }
+
+            /// Return the principal natural logarithm of `self`, whose
+            /// imaginary part lies in `[-π, π]`; it is `-π` only on the
+            /// negative real axis with a negative zero imaginary part.
+            pub fn ln(self) -> Self {
+                Complex {
+                    re: self.abs().ln(),
+                    im: self.arg(),
+                }
+            }
+
+            /// Return the principal square root of `self`, whose real part is
+            /// never negative.
+            pub fn sqrt(self) -> Self {
+                if self.is_zero() {
+                    return Complex {
+                        re: 0.0,
+                        im: self.im,
+                    };
+                }
+
+                // Near the top of the range, `r + |re|` below could overflow.
+                // sqrt(z) == 2 sqrt(z / 4), and scaling by powers of two is
+                // exact.
+                let limit = $float::MAX / 4.0;
+                if self.re.is_finite() && self.im.is_finite()
+                    && (self.re.abs() > limit || self.im.abs() > limit)
+                {
+                    return self.scale(0.25).sqrt().scale(2.0);
+                }
+
+                // Compute whichever component avoids cancellation directly,
+                // and derive the other from `2 * re * im == self.im`.
+                let r = self.abs();
+                if self.re >= 0.0 {
+                    let t = ((r + self.re) / 2.0).sqrt();
+                    Complex {
+                        re: t,
+                        im: self.im / (2.0 * t),
+                    }
+                } else {
+                    let t = ((r - self.re) / 2.0).sqrt();
+                    Complex {
+                        re: self.im.abs() / (2.0 * t),
+                        im: t.copysign(self.im),
+                    }
+                }
+            }
+
+            /// Return `self` raised to the complex power `exp`.
+            ///
+            /// Zero raised to `exp` is `1` if `exp` is zero, `0` if `exp` has
+            /// a positive real part, `∞` if `exp` is a negative real number,
+            /// and otherwise has no meaningful value, so it is NaN.
+            pub fn powc(self, exp: Self) -> Self {
+                if self.is_zero() {
+                    return if exp.is_zero() {
+                        Self::real(1.0)
+                    } else if exp.re > 0.0 {
+                        Self::real(0.0)
+                    } else if exp.re < 0.0 && exp.im == 0.0 {
+                        Self::real($float::INFINITY)
+                    } else {
+                        Complex { re: $float::NAN, im: $float::NAN }
+                    };
+                }
+                (exp * self.ln()).exp()
+            }
+
+            /// Return `self` raised to the real power `exp`. Zero raised to a
+            /// negative power is `∞`.
+            pub fn powf(self, exp: $float) -> Self {
+                if self.is_zero() {
+                    return self.powc(Self::real(exp));
+                }
+                let Polar { r, theta } = self.to_polar();
+                Self::from_polar(r.powf(exp), theta * exp)
+            }
+
+            /// Return the sine of `self`.
+            pub fn sin(self) -> Self {
+                Complex {
+                    re: self.re.sin() * self.im.cosh(),
+                    im: self.re.cos() * self.im.sinh(),
+                }
+            }
+
+            /// Return the cosine of `self`.
+            pub fn cos(self) -> Self {
+                Complex {
+                    re: self.re.cos() * self.im.cosh(),
+                    im: -(self.re.sin() * self.im.sinh()),
+                }
+            }
+
+            /// Return the tangent of `self`.
+            pub fn tan(self) -> Self {
+                // tan z = -i tanh(iz).
+                let w = Complex { re: -self.im, im: self.re }.tanh();
+                Complex { re: w.im, im: -w.re }
+            }
+
+            /// Return the hyperbolic sine of `self`.
+            pub fn sinh(self) -> Self {
+                Complex {
+                    re: self.re.sinh() * self.im.cos(),
+                    im: self.re.cosh() * self.im.sin(),
+                }
+            }
+
+            /// Return the hyperbolic cosine of `self`.
+            pub fn cosh(self) -> Self {
+                Complex {
+                    re: self.re.cosh() * self.im.cos(),
+                    im: self.re.sinh() * self.im.sin(),
+                }
+            }
+
+            /// Return the hyperbolic tangent of `self`.
+            pub fn tanh(self) -> Self {
+                // Once cosh 2re dwarfs cos 2im, the quotient below is ±1 to
+                // within rounding, and the numerator and denominator overflow
+                // soon after, so use its limit instead.
+                let big = 1.0 - $float::EPSILON.ln() / 2.0;
+                if self.re.abs() > big {
+                    return Complex {
+                        re: (1.0 as $float).copysign(self.re),
+                        im: 2.0 * (2.0 * self.im).sin() * (-2.0 * self.re.abs()).exp(),
+                    };
+                }
+                let (two_re, two_im) = (2.0 * self.re, 2.0 * self.im);
+                let den = two_re.cosh() + two_im.cos();
+                Complex {
+                    re: two_re.sinh() / den,
+                    im: two_im.sin() / den,
+                }
+            }
+
+            /// Return `1 + self` and `1 - self`, negating the components
+            /// rather than subtracting them, so that signed zeros carry over.
+            fn one_plus_minus(self) -> (Self, Self) {
+                (
+                    Complex { re: 1.0 + self.re, im: self.im },
+                    Complex { re: 1.0 - self.re, im: -self.im },
+                )
+            }
+
+            /// Return the principal arcsine, by Kahan's formula in terms of
+            /// `sqrt(1 - z)` and `sqrt(1 + z)`, which respects signed zeros on
+            /// the cuts.
+            pub fn asin(self) -> Self {
+                let (plus, minus) = self.one_plus_minus();
+                let (s1, s2) = (minus.sqrt(), plus.sqrt());
+                Complex {
+                    re: self.re.atan2(s1.re * s2.re - s1.im * s2.im),
+                    im: (s1.re * s2.im - s1.im * s2.re).asinh(),
+                }
+            }
+
+            /// Return the principal arccosine, by Kahan's formula in terms of
+            /// `sqrt(1 - z)` and `sqrt(1 + z)`.
+            pub fn acos(self) -> Self {
+                let (plus, minus) = self.one_plus_minus();
+                let (s1, s2) = (minus.sqrt(), plus.sqrt());
+                Complex {
+                    re: 2.0 * s1.re.atan2(s2.re),
+                    im: (s2.re * s1.im - s2.im * s1.re).asinh(),
+                }
+            }
+
+            /// Return the principal arctangent: `-i atanh(iz)`.
+            pub fn atan(self) -> Self {
+                let w = Complex { re: -self.im, im: self.re }.atanh();
+                Complex { re: w.im, im: -w.re }
+            }
+
+            /// Return the principal inverse hyperbolic sine: `-i asin(iz)`.
+            pub fn asinh(self) -> Self {
+                let w = Complex { re: -self.im, im: self.re }.asin();
+                Complex { re: w.im, im: -w.re }
+            }
+
+            /// Return the principal inverse hyperbolic cosine, by Kahan's
+            /// formula in terms of `sqrt(z - 1)` and `sqrt(z + 1)`.
+            pub fn acosh(self) -> Self {
+                let s1 = Complex { re: self.re - 1.0, im: self.im }.sqrt();
+                let s2 = Complex { re: self.re + 1.0, im: self.im }.sqrt();
+                Complex {
+                    re: (s1.re * s2.re + s1.im * s2.im).asinh(),
+                    im: 2.0 * s1.im.atan2(s2.re),
+                }
+            }
+
+            /// Return the principal inverse hyperbolic tangent:
+            /// `(ln(1 + z) - ln(1 - z)) / 2`.
+            pub fn atanh(self) -> Self {
+                if self.im == 0.0 && self.re.abs() == 1.0 {
+                    return Complex {
+                        re: $float::INFINITY.copysign(self.re),
+                        im: self.im,
+                    };
+                }
+                let (plus, minus) = self.one_plus_minus();
+                (plus.ln() - minus.ln()).scale(0.5)
+            }
+        }
+    };
+}
+
+define_transcendental!(f32);
+define_transcendental!(f64);
+
+#[cfg(test)]
+fn c(re: f64, im: f64) -> Complex<f64> {
+    Complex::new(re, im)
+}
+
+#[cfg(test)]
+fn close(a: Complex<f64>, b: Complex<f64>) -> bool {
+    let scale = 1.0_f64.max(b.re.abs()).max(b.im.abs());
+    (a.re - b.re).abs() <= 1e-12 * scale && (a.im - b.im).abs() <= 1e-12 * scale
+}
+
+#[cfg(test)]
+fn samples() -> Vec<Complex<f64>> {
+    vec![
+        c(0.5, 0.25),
+        c(-0.75, 0.3),
+        c(1.5, -2.0),
+        c(-2.0, -0.5),
+        c(0.0, 0.8),
+        c(0.3, 0.0),
+    ]
+}
+
+#[test]
+fn test_special_values() {
+    use std::f64::consts::PI;
+
+    let i = c(0.0, 1.0);
+    assert!(close(c(0.0, PI).exp(), c(-1.0, 0.0)));
+    assert!(close(c(-1.0, 0.0).ln(), c(0.0, PI)));
+    assert!(close(c(-1.0, 0.0).sqrt(), i));
+    assert!(close(i.powc(i), c((-PI / 2.0).exp(), 0.0)));
+    assert!(close(c(2.0, 0.0).powf(0.5), c(2f64.sqrt(), 0.0)));
+    assert!(close(i.powf(2.0), c(-1.0, 0.0)));
+    assert_eq!(c(0.0, 0.0).powf(0.0), c(1.0, 0.0));
+
+    // Powers of zero: 0 for exponents with a positive real part, ∞ for
+    // negative real exponents, and undefined otherwise.
+    let zero = c(0.0, 0.0);
+    let undefined = |z: Complex<f64>| z.re.is_nan() && z.im.is_nan();
+    assert_eq!(zero.powc(c(2.0, 5.0)), zero);
+    assert_eq!(zero.powf(0.5), zero);
+    assert_eq!(zero.powf(-1.0), c(f64::INFINITY, 0.0));
+    assert_eq!(zero.powc(c(-1.0, 0.0)), c(f64::INFINITY, 0.0));
+    assert!(undefined(zero.powc(i)));
+    assert!(undefined(zero.powc(c(-1.0, 1.0))));
+    assert!(undefined(zero.powf(f64::NAN)));
+    assert_eq!(i.atan(), c(0.0, f64::INFINITY));
+    assert_eq!(c(-1.0, 0.0).atanh(), c(-f64::INFINITY, 0.0));
+}
+
+#[test]
+fn test_branch_cuts() {
+    use std::f64::consts::PI;
+
+    // The sign of a zero imaginary part selects the side of the cut.
+    assert_eq!(c(-4.0, 0.0).sqrt(), c(0.0, 2.0));
+    assert_eq!(c(-4.0, -0.0).sqrt(), c(0.0, -2.0));
+    assert!(close(c(-1.0, 0.0).ln(), c(0.0, PI)));
+    assert!(close(c(-1.0, -0.0).ln(), c(0.0, -PI)));
+
+    // The inverse functions follow C99 on their cuts, too.
+    let t = 3f64.sqrt().asinh();
+    assert!(close(c(2.0, 0.0).asin(), c(PI / 2.0, t)));
+    assert!(close(c(2.0, -0.0).asin(), c(PI / 2.0, -t)));
+    assert!(close(c(-2.0, 0.0).asin(), c(-PI / 2.0, t)));
+    assert!(close(c(2.0, 0.0).acos(), c(0.0, -t)));
+    assert!(close(c(2.0, -0.0).acos(), c(0.0, t)));
+    assert!(close(c(-2.0, 0.0).acos(), c(PI, -t)));
+    assert!(close(c(0.0, 2.0).atan(), c(PI / 2.0, 3f64.ln() / 2.0)));
+    assert!(close(c(-0.0, 2.0).atan(), c(-PI / 2.0, 3f64.ln() / 2.0)));
+    assert!(close(c(0.0, 2.0).asinh(), c(t, PI / 2.0)));
+    assert!(close(c(-0.0, 2.0).asinh(), c(-t, PI / 2.0)));
+    assert!(close(c(-2.0, 0.0).acosh(), c(t, PI)));
+    assert!(close(c(-2.0, -0.0).acosh(), c(t, -PI)));
+    assert!(close(c(0.5, -0.0).acosh(), c(0.0, -(0.5f64).acos())));
+    assert!(close(c(2.0, 0.0).atanh(), c(3f64.ln() / 2.0, PI / 2.0)));
+    assert!(close(c(2.0, -0.0).atanh(), c(3f64.ln() / 2.0, -PI / 2.0)));
+
+    // Principal square roots never have a negative real part.
+    for z in samples() {
+        assert!(z.sqrt().re >= 0.0);
+    }
+
+    // Principal logarithms have imaginary parts in [-π, π].
+    for z in samples() {
+        let im = z.ln().im;
+        assert!((-PI..=PI).contains(&im));
+    }
+}
+
+#[test]
+fn test_identities() {
+    let one = c(1.0, 0.0);
+    for z in samples() {
+        assert!(close(z.ln().exp(), z));
+        assert!(close(z.sqrt() * z.sqrt(), z));
+        assert!(close(z.powf(3.0), z * z * z));
+        assert!(close(z.powc(c(2.0, 0.0)), z * z));
+
+        let (s, c) = (z.sin(), z.cos());
+        assert!(close(s * s + c * c, one));
+        assert!(close(z.tan(), s / c));
+
+        let (sh, ch) = (z.sinh(), z.cosh());
+        assert!(close(ch * ch - sh * sh, one));
+        assert!(close(z.tanh(), sh / ch));
+    }
+}
+
+#[test]
+fn test_large_arguments() {
+    // cosh and sinh overflow here, but tan and tanh approach ±i and ±1.
+    assert!(close(c(1.0, 400.0).tan(), c(0.0, 1.0)));
+    assert!(close(c(1.0, -400.0).tan(), c(0.0, -1.0)));
+    assert!(close(c(400.0, 1.0).tanh(), c(1.0, 0.0)));
+    assert!(close(c(-1e300, 1.0).tanh(), c(-1.0, 0.0)));
+    assert!(close(c(20.0, 0.5).tanh(), c(20.0, 0.5).sinh() / c(20.0, 0.5).cosh()));
+    let w = Complex::new(2.0f32, 60.0).tan();
+    assert!(w.re.abs() < 1e-6 && (w.im - 1.0).abs() < 1e-6);
+
+    // Square roots of huge values mustn't overflow along the way.
+    let root_max = f64::MAX.sqrt();
+    assert!(close(c(1e308, 1e308).sqrt(), c(1.0, 1.0).sqrt().scale(1e154)));
+    assert!(close(c(f64::MAX, 0.0).sqrt(), c(root_max, 0.0)));
+    assert!(close(c(-f64::MAX, -0.0).sqrt(), c(0.0, -root_max)));
+    assert!(close(c(f64::MAX, f64::MAX).sqrt(), c(1.0, 1.0).sqrt().scale(root_max)));
+    let w = Complex::new(f32::MAX, f32::MAX).sqrt();
+    let expected = Complex::new(1.0f32, 1.0).sqrt().scale(f32::MAX.sqrt());
+    assert!((w.re / expected.re - 1.0).abs() < 1e-6 && (w.im / expected.im - 1.0).abs() < 1e-6);
+    assert_eq!(c(f64::INFINITY, 1.0).sqrt(), c(f64::INFINITY, 0.0));
+}
+
+#[test]
+fn test_inverses() {
+    for z in samples() {
+        assert!(close(z.asin().sin(), z));
+        assert!(close(z.acos().cos(), z));
+        assert!(close(z.atan().tan(), z));
+        assert!(close(z.asinh().sinh(), z));
+        assert!(close(z.acosh().cosh(), z));
+        assert!(close(z.atanh().tanh(), z));
+    }
+
+    // Near the origin, the functions invert their inverses, too.
+    let z = c(0.3, -0.2);
+    assert!(close(z.sin().asin(), z));
+    assert!(close(z.tan().atan(), z));
+    assert!(close(z.sinh().asinh(), z));
+    assert!(close(z.tanh().atanh(), z));
+}
+
+#[test]
+fn test_f32() {
+    let z = Complex::new(0.5f32, -1.25f32);
+    let w = z.ln().exp();
+    assert!((w.re - z.re).abs() < 1e-6 && (w.im - z.im).abs() < 1e-6);
+    let w = z.sqrt() * z.sqrt();
+    assert!((w.re - z.re).abs() < 1e-6 && (w.im - z.im).abs() < 1e-6);
+    assert_eq!(Complex::new(-9.0f32, 0.0).sqrt(), Complex::new(0.0, 3.0));
+}
@@ -0,0 +1,384 @@
+//! A `Queue` that asynchronous tasks can share, parking when it's empty.
+
+use crate::sync::{Closed, TryPopError};
+use crate::Queue;
+use futures::{Sink, Stream};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// A first-in, first-out queue for passing values between asynchronous tasks.
+///
+/// `pop().await` parks the task until a value arrives, and each `push` wakes
+/// one parked task. The queue is also a `Stream` of the values pushed, ending
+/// once it's closed and drained, and a `Sink` that pushes the values sent to
+/// it, closing the queue when the sink is closed.
+///
+/// Cloning an `AsyncQueue` gives another handle to the same queue, so give
+/// each producer and consumer its own.
+pub struct AsyncQueue<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+    /// This handle's place in line while it's being polled as a `Stream`.
+    slot: Option<u64>,
+}
+
+struct Shared<T> {
+    queue: Queue<T>,
+    /// The wakers of tasks waiting for a value, by slot. Each waiting `Pop`
+    /// future or stream takes the next slot number when it first parks, so
+    /// the longest-waiting task comes first, and a future that goes away can
+    /// remove its own waker.
+    wakers: BTreeMap<u64, Waker>,
+    next_slot: u64,
+    closed: bool,
+}
+
+impl<T> AsyncQueue<T> {
+    pub fn new() -> AsyncQueue<T> {
+        AsyncQueue {
+            shared: Arc::new(Mutex::new(Shared {
+                queue: Queue::new(),
+                wakers: BTreeMap::new(),
+                next_slot: 0,
+                closed: false,
+            })),
+            slot: None,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared<T>> {
+        self.shared.lock().unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Add `value` to the back of the queue, and wake one task waiting to
+    /// pop. If the queue is closed, return the value in an error.
+    pub fn push(&self, value: T) -> Result<(), Closed<T>> {
+        let maybe_waker = {
+            let mut guard = self.lock();
+            if guard.closed {
+                return Err(Closed(value));
+            }
+            guard.queue.push(value);
+            guard.wakers.pop_first().map(|(_, waker)| waker)
+        };
+
+        if let Some(waker) = maybe_waker {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Return a future that removes the value at the front of the queue,
+    /// waiting for one to arrive if the queue is empty. The future produces
+    /// `None` once the queue is empty and closed.
+    pub fn pop(&self) -> Pop<'_, T> {
+        Pop { queue: self, slot: None }
+    }
+
+    /// Remove the value at the front of the queue, if there is one, without
+    /// waiting.
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        let mut guard = self.lock();
+        match guard.queue.pop() {
+            Some(value) => Ok(value),
+            None if guard.closed => Err(TryPopError::Closed),
+            None => Err(TryPopError::Empty),
+        }
+    }
+
+    /// Close the queue, waking every waiting task. Values already in the
+    /// queue can still be popped.
+    pub fn close(&self) {
+        let wakers = {
+            let mut guard = self.lock();
+            guard.closed = true;
+            std::mem::take(&mut guard.wakers)
+        };
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// Pop a value if there is one, and otherwise arrange for the task to be
+    /// woken when one arrives, in the waker slot `slot`, taking a new slot if
+    /// it's `None`. Return `Ready(None)` if the queue is empty and closed.
+    /// This serves both `Pop` and `Stream`.
+    fn poll_pop(&self, slot: &mut Option<u64>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut guard = self.lock();
+        let ready = match guard.queue.pop() {
+            Some(value) => Some(value),
+            None if guard.closed => None,
+            None => {
+                let id = *slot.get_or_insert_with(|| {
+                    guard.next_slot += 1;
+                    guard.next_slot - 1
+                });
+                // Polling again replaces the waker, but keeps our place in
+                // line.
+                guard.wakers.insert(id, cx.waker().clone());
+                return Poll::Pending;
+            }
+        };
+        if let Some(id) = slot.take() {
+            guard.wakers.remove(&id);
+        }
+        Poll::Ready(ready)
+    }
+
+    /// Give up the waker slot `slot`, if we hold one. If `push` already woke
+    /// it and there are values left, pass the wakeup on to the next waiting
+    /// task, since we may have been the one task it chose to wake.
+    fn cancel(&self, slot: &mut Option<u64>) {
+        let id = match slot.take() {
+            Some(id) => id,
+            None => return,
+        };
+        let maybe_waker = {
+            let mut guard = self.lock();
+            if guard.wakers.remove(&id).is_some() || guard.queue.is_empty() {
+                return;
+            }
+            guard.wakers.pop_first().map(|(_, waker)| waker)
+        };
+        if let Some(waker) = maybe_waker {
+            waker.wake();
+        }
+    }
+}
+
+/// The clone is a new handle, with no place in line of its own.
+impl<T> Clone for AsyncQueue<T> {
+    fn clone(&self) -> Self {
+        AsyncQueue { shared: self.shared.clone(), slot: None }
+    }
+}
+
+impl<T> Drop for AsyncQueue<T> {
+    fn drop(&mut self) {
+        let mut slot = self.slot.take();
+        self.cancel(&mut slot);
+    }
+}
+
+impl<T> Default for AsyncQueue<T> {
+    fn default() -> Self {
+        AsyncQueue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for AsyncQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let guard = self.lock();
+        f.debug_struct("AsyncQueue")
+            .field("queue", &guard.queue)
+            .field("closed", &guard.closed)
+            .finish()
+    }
+}
+
+/// The future returned by `AsyncQueue::pop`.
+pub struct Pop<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    slot: Option<u64>,
+}
+
+impl<'a, T> Future for Pop<'a, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = &mut *self;
+        this.queue.poll_pop(&mut this.slot, cx)
+    }
+}
+
+impl<'a, T> Drop for Pop<'a, T> {
+    fn drop(&mut self) {
+        self.queue.cancel(&mut self.slot);
+    }
+}
+
+impl<T> Stream for AsyncQueue<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let mut slot = self.slot.take();
+        let poll = self.poll_pop(&mut slot, cx);
+        self.slot = slot;
+        poll
+    }
+}
+
+/// The queue has no capacity limit, so it's always ready for more.
+impl<T> Sink<T> for AsyncQueue<T> {
+    type Error = Closed<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed<T>>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: T) -> Result<(), Closed<T>> {
+        self.push(value)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed<T>>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed<T>>> {
+        AsyncQueue::close(&self);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+use futures::executor::{block_on, LocalPool};
+#[cfg(test)]
+use futures::task::LocalSpawnExt;
+#[cfg(test)]
+use futures::{SinkExt, StreamExt};
+
+#[test]
+fn test_push_pop() {
+    let q = AsyncQueue::new();
+    assert_eq!(q.try_pop(), Err(TryPopError::Empty));
+    q.push(1).unwrap();
+    q.push(2).unwrap();
+    assert_eq!(q.len(), 2);
+    assert_eq!(block_on(q.pop()), Some(1));
+    assert_eq!(q.try_pop(), Ok(2));
+
+    q.push(3).unwrap();
+    q.close();
+    assert_eq!(q.push(4), Err(Closed(4)));
+    assert_eq!(block_on(q.pop()), Some(3));
+    assert_eq!(block_on(q.pop()), None);
+    assert_eq!(q.try_pop(), Err(TryPopError::Closed));
+}
+
+#[test]
+fn test_sink() {
+    let mut q = AsyncQueue::new();
+    block_on(async {
+        q.send("a").await.unwrap();
+        q.send_all(&mut futures::stream::iter(vec![Ok("b"), Ok("c")])).await.unwrap();
+        SinkExt::close(&mut q).await.unwrap();
+    });
+    assert!(q.is_closed());
+    assert_eq!(block_on(q.collect::<Vec<_>>()), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn test_pop_waits_for_push() {
+    let q = AsyncQueue::new();
+    let producer = {
+        let q = q.clone();
+        std::thread::spawn(move || {
+            for i in 0..100 {
+                std::thread::sleep(std::time::Duration::from_micros(100));
+                q.push(i).unwrap();
+            }
+            q.close();
+        })
+    };
+
+    let received: Vec<i32> = block_on(q.collect());
+    producer.join().unwrap();
+    assert_eq!(received, (0..100).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_shared_work() {
+    // Several consumer tasks share one queue on a single-threaded executor.
+    // Every value must reach some consumer, and closing must end them all.
+    let mut pool = LocalPool::new();
+    let q = AsyncQueue::new();
+    let mut results = Vec::new();
+    for _ in 0..4 {
+        let q = q.clone();
+        results.push(pool.spawner().spawn_local_with_handle(async move {
+            let mut sum = 0;
+            let mut count = 0;
+            while let Some(n) = q.pop().await {
+                sum += n;
+                count += 1;
+            }
+            (sum, count)
+        }).unwrap());
+    }
+    pool.run_until_stalled();
+
+    for n in 1..=1000 {
+        q.push(n).unwrap();
+        // Give the consumers a turn now and then.
+        if n % 10 == 0 {
+            pool.run_until_stalled();
+        }
+    }
+    q.close();
+    let totals = pool.run_until(futures::future::join_all(results));
+
+    assert_eq!(totals.iter().map(|&(sum, _)| sum).sum::<i32>(), 500_500);
+    assert_eq!(totals.iter().map(|&(_, count)| count).sum::<i32>(), 1000);
+    assert!(q.is_closed());
+}
+
+#[test]
+fn test_dropped_pop_passes_wakeup_on() {
+    let q = AsyncQueue::new();
+    let mut pool = LocalPool::new();
+
+    // Park two pops, then drop the one the push will wake.
+    let mut first = Box::pin(q.pop());
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(first.as_mut().poll(&mut cx).is_pending());
+
+    let second = {
+        let q = q.clone();
+        pool.spawner().spawn_local_with_handle(async move { q.pop().await }).unwrap()
+    };
+    pool.run_until_stalled();
+
+    q.push('x').unwrap();
+    drop(first);
+    assert_eq!(pool.run_until(second), Some('x'));
+}
+
+#[test]
+fn test_dropped_pop_leaves_no_waker() {
+    let q = AsyncQueue::new();
+    let mut pool = LocalPool::new();
+
+    // Park a pop and drop it while the queue is still empty; a push mustn't
+    // spend its wakeup on the waker it left behind.
+    let mut abandoned = Box::pin(q.pop());
+    let waker = futures::task::noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    assert!(abandoned.as_mut().poll(&mut cx).is_pending());
+    drop(abandoned);
+
+    let waiting = {
+        let q = q.clone();
+        pool.spawner().spawn_local_with_handle(async move { q.pop().await }).unwrap()
+    };
+    pool.run_until_stalled();
+
+    q.push('x').unwrap();
+    assert_eq!(pool.run_until(waiting), Some('x'));
+    assert!(q.is_empty());
+}
@@ -0,0 +1,617 @@
+//! A queue kept in files, so that its contents survive a crash.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::TryInto;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Each record starts with its payload's length, and a CRC-32 of that length
+/// and the payload, both little-endian `u32`s. Covering the length means a
+/// stretch of zeros isn't a valid empty record.
+const HEADER_LEN: u64 = 8;
+
+/// The segment size `DiskQueue::open` uses.
+const DEFAULT_SEGMENT_SIZE: u64 = 1 << 20;
+
+/// A first-in, first-out queue stored in a directory, for handing work from
+/// one run of a program to the next.
+///
+/// Pushed values are serialized with `bincode` and appended to a log, which is
+/// split into segment files of roughly equal size. A separate checkpoint file
+/// records how far into the log `pop` has got. Both are synced to disk before
+/// `push` or `pop` returns, so after a crash, `DiskQueue::open` recovers
+/// exactly the values that were pushed and not yet popped: no value `pop` has
+/// returned is delivered again, and none `push` accepted is lost. A record
+/// that was only partly written when the crash happened is discarded, along
+/// with anything after it, like the zeros some filesystems leave at the end
+/// of a file after a crash; damage anywhere else makes `open` fail with
+/// `ErrorKind::InvalidData`.
+///
+/// To make sure a value is processed at least once, even if the consumer
+/// crashes, `peek` at it, process it, and only then `pop` it.
+///
+/// Once every record in a segment has been popped, the segment is deleted.
+/// Only one `DiskQueue` should have a given directory open at a time.
+pub struct DiskQueue<T> {
+    dir: PathBuf,
+    segment_size: u64,
+    /// The segments still on disk, oldest first. We pop from the first and
+    /// push onto the last.
+    segments: Vec<Segment>,
+    /// The last segment, open for appending.
+    writer: File,
+    /// The first segment, positioned at the next record to pop.
+    reader: BufReader<File>,
+    /// The position of `reader` in the first segment.
+    offset: u64,
+    /// The sequence numbers of the next record to pop, and the next to push.
+    head: u64,
+    tail: u64,
+    marker: PhantomData<fn(T) -> T>,
+}
+
+struct Segment {
+    /// The sequence number of the segment's first record, which also names
+    /// its file.
+    first: u64,
+    /// The length of the segment's file.
+    bytes: u64,
+}
+
+impl<T: Serialize + DeserializeOwned> DiskQueue<T> {
+    /// Open the queue stored in `dir`, creating the directory and an empty
+    /// queue if they don't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> io::Result<DiskQueue<T>> {
+        DiskQueue::with_segment_size(dir, DEFAULT_SEGMENT_SIZE)
+    }
+
+    /// Like `open`, but start a new segment whenever a record would take the
+    /// current one past `segment_size` bytes. A record larger than that gets
+    /// a segment to itself. Panic if `segment_size` is zero.
+    pub fn with_segment_size<P>(dir: P, segment_size: u64) -> io::Result<DiskQueue<T>>
+    where
+        P: AsRef<Path>,
+    {
+        assert!(segment_size > 0, "DiskQueue segment size must be positive");
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut firsts = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let name = entry?.file_name();
+            let first = name.to_str()
+                .and_then(|name| name.strip_suffix(".log"))
+                .and_then(|stem| stem.parse::<u64>().ok());
+            if let Some(first) = first {
+                firsts.push(first);
+            }
+        }
+        firsts.sort_unstable();
+
+        // A crash may have come between moving the checkpoint on to a new
+        // segment and deleting the old ones.
+        let checkpoint = read_checkpoint(&dir)?;
+        if let Some((segment, _)) = checkpoint {
+            for first in firsts.iter().filter(|&&first| first < segment) {
+                fs::remove_file(segment_path(&dir, *first))?;
+            }
+            firsts.retain(|&first| first >= segment);
+        }
+        if firsts.is_empty() {
+            let first = checkpoint.map_or(0, |(segment, _)| segment);
+            File::create(segment_path(&dir, first))?;
+            sync_dir(&dir)?;
+            firsts.push(first);
+        }
+
+        let mut segments = Vec::new();
+        let mut tail = firsts[0];
+        for (i, &first) in firsts.iter().enumerate() {
+            if first != tail {
+                return Err(corrupt(format!("segment {} is missing records", first)));
+            }
+            let path = segment_path(&dir, first);
+            let len = fs::metadata(&path)?.len();
+            let (records, bytes) = scan(&path, len)?;
+            if bytes < len {
+                // A crash can only damage the end of the last segment, after
+                // its last complete record. Damage anywhere else would lose
+                // records we promised to keep.
+                if i + 1 < firsts.len() || !is_torn_tail(&path, bytes)? {
+                    return Err(corrupt(format!("segment {} has a damaged record", first)));
+                }
+                // Nobody could have popped the torn record, so drop it.
+                let file = OpenOptions::new().write(true).open(&path)?;
+                file.set_len(bytes)?;
+                file.sync_all()?;
+            }
+            segments.push(Segment { first, bytes });
+            tail += records;
+        }
+
+        let (segment, offset) = checkpoint.unwrap_or((firsts[0], 0));
+        if segment != firsts[0] {
+            return Err(corrupt(format!("checkpoint names missing segment {}", segment)));
+        }
+        let path = segment_path(&dir, segment);
+        let (popped, end) = scan(&path, offset)?;
+        if end != offset {
+            return Err(corrupt("checkpoint is not at a record boundary".to_string()));
+        }
+
+        let mut reader = BufReader::new(File::open(&path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+        let writer = OpenOptions::new()
+            .append(true)
+            .open(segment_path(&dir, segments.last().unwrap().first))?;
+        let mut queue = DiskQueue {
+            dir,
+            segment_size,
+            segments,
+            writer,
+            reader,
+            offset,
+            head: segment + popped,
+            tail,
+            marker: PhantomData,
+        };
+        queue.compact()?;
+        Ok(queue)
+    }
+
+    pub fn len(&self) -> usize {
+        (self.tail - self.head) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+
+    /// Add `value` to the back of the queue, returning once it's safely on
+    /// disk.
+    pub fn push(&mut self, value: &T) -> io::Result<()> {
+        let payload = bincode::serialize(value)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let len: u32 = payload.len().try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "record too large"))?;
+        let record_len = HEADER_LEN + u64::from(len);
+
+        let bytes = self.segments.last().unwrap().bytes;
+        if bytes > 0 && bytes + record_len > self.segment_size {
+            self.roll()?;
+        }
+
+        let mut record = Vec::with_capacity(record_len as usize);
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&checksum(len, &payload).to_le_bytes());
+        record.extend_from_slice(&payload);
+
+        let writer = &mut self.writer;
+        let active = self.segments.last_mut().unwrap();
+        if let Err(e) = writer.write_all(&record).and_then(|()| writer.sync_data()) {
+            // Don't leave a partial record for the next one to follow.
+            let _ = writer.set_len(active.bytes);
+            return Err(e);
+        }
+        active.bytes += record_len;
+        self.tail += 1;
+        Ok(())
+    }
+
+    /// Remove the value at the front of the queue, and return it once the
+    /// removal is safely on disk.
+    pub fn pop(&mut self) -> io::Result<Option<T>> {
+        let (value, record_len) = match self.read_front()? {
+            Some(front) => front,
+            None => return Ok(None),
+        };
+        // Commit the pop before changing anything, so that if the checkpoint
+        // can't be written, the value stays at the front.
+        if let Err(e) = self.write_checkpoint(self.segments[0].first, self.offset + record_len) {
+            self.reader.seek(SeekFrom::Start(self.offset))?;
+            return Err(e);
+        }
+        self.offset += record_len;
+        self.head += 1;
+
+        // The pop is committed, so don't lose the value if compaction fails:
+        // the next pop or push tries again.
+        let _ = self.compact();
+        Ok(Some(value))
+    }
+
+    /// Return the value `pop` would remove next, without removing it.
+    pub fn peek(&mut self) -> io::Result<Option<T>> {
+        let front = self.read_front()?;
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        Ok(front.map(|(value, _)| value))
+    }
+
+    /// Read and deserialize the record at `reader`, returning it with its
+    /// length. On failure, leave `reader` where it was.
+    fn read_front(&mut self) -> io::Result<Option<(T, u64)>> {
+        if self.head == self.tail {
+            return Ok(None);
+        }
+        let limit = self.segments[0].bytes - self.offset;
+        let result = read_record(&mut self.reader, limit).and_then(|payload| {
+            let payload = payload
+                .ok_or_else(|| corrupt("record damaged since it was written".to_string()))?;
+            let value = bincode::deserialize(&payload)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok((value, HEADER_LEN + payload.len() as u64))
+        });
+        if result.is_err() {
+            self.reader.seek(SeekFrom::Start(self.offset))?;
+        }
+        result.map(Some)
+    }
+
+    /// Start a new segment, beginning with the next record pushed.
+    fn roll(&mut self) -> io::Result<()> {
+        self.writer = OpenOptions::new()
+            .append(true)
+            .create_new(true)
+            .open(segment_path(&self.dir, self.tail))?;
+        sync_dir(&self.dir)?;
+        self.segments.push(Segment { first: self.tail, bytes: 0 });
+        self.compact()
+    }
+
+    /// Delete the first segment if every record in it has been popped and
+    /// there's a later segment to move on to, and repeat.
+    fn compact(&mut self) -> io::Result<()> {
+        while self.segments.len() > 1 && self.offset == self.segments[0].bytes {
+            // Move the checkpoint before deleting anything, so a crash can't
+            // leave it naming a deleted segment. If the deletion fails, `open`
+            // removes the file next time.
+            let next = self.segments[1].first;
+            let reader = BufReader::new(File::open(segment_path(&self.dir, next))?);
+            self.write_checkpoint(next, 0)?;
+            let consumed = self.segments.remove(0);
+            self.reader = reader;
+            self.offset = 0;
+            fs::remove_file(segment_path(&self.dir, consumed.first))?;
+        }
+        Ok(())
+    }
+
+    /// Record that the next record to pop is at `offset` in the segment
+    /// starting with record `segment`. Write a new checkpoint file and rename
+    /// it over the old one, so that a crash leaves one or the other intact.
+    fn write_checkpoint(&self, segment: u64, offset: u64) -> io::Result<()> {
+        let mut contents = Vec::with_capacity(20);
+        contents.extend_from_slice(&segment.to_le_bytes());
+        contents.extend_from_slice(&offset.to_le_bytes());
+        let checksum = crc32fast::hash(&contents);
+        contents.extend_from_slice(&checksum.to_le_bytes());
+
+        let temporary = self.dir.join("checkpoint.tmp");
+        let mut file = File::create(&temporary)?;
+        file.write_all(&contents)?;
+        file.sync_data()?;
+        fs::rename(&temporary, self.dir.join("checkpoint"))?;
+        sync_dir(&self.dir)
+    }
+}
+
+impl<T> fmt::Debug for DiskQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DiskQueue")
+            .field("dir", &self.dir)
+            .field("len", &(self.tail - self.head))
+            .field("segments", &self.segments.len())
+            .finish()
+    }
+}
+
+fn segment_path(dir: &Path, first: u64) -> PathBuf {
+    dir.join(format!("{:020}.log", first))
+}
+
+fn corrupt(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Return the segment and offset saved by `write_checkpoint`, or `None` if
+/// there's no checkpoint yet.
+fn read_checkpoint(dir: &Path) -> io::Result<Option<(u64, u64)>> {
+    let contents = match fs::read(dir.join("checkpoint")) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if contents.len() != 20 || crc32fast::hash(&contents[..16]).to_le_bytes() != contents[16..] {
+        return Err(corrupt("checkpoint is damaged".to_string()));
+    }
+    let segment = u64::from_le_bytes(contents[..8].try_into().unwrap());
+    let offset = u64::from_le_bytes(contents[8..16].try_into().unwrap());
+    Ok(Some((segment, offset)))
+}
+
+/// Read the record at `reader`'s position, or return `None` if the next
+/// `limit` bytes don't hold a complete record with a correct checksum.
+fn read_record<R: Read>(reader: &mut R, limit: u64) -> io::Result<Option<Vec<u8>>> {
+    let mut header = [0; HEADER_LEN as usize];
+    if limit < HEADER_LEN || !read_full(reader, &mut header)? {
+        return Ok(None);
+    }
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap());
+    let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+    if u64::from(len) > limit - HEADER_LEN {
+        return Ok(None);
+    }
+    let mut payload = vec![0; len as usize];
+    if !read_full(reader, &mut payload)? || self::checksum(len, &payload) != checksum {
+        return Ok(None);
+    }
+    Ok(Some(payload))
+}
+
+fn checksum(len: u32, payload: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&len.to_le_bytes());
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+/// Return true if no complete record with a correct checksum starts anywhere
+/// in the segment at `path` from `offset` on, so that those bytes can only be
+/// a torn append: part of a record, or zeros, or both.
+fn is_torn_tail(path: &Path, offset: u64) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    for start in 0..tail.len() {
+        let mut rest = &tail[start..];
+        let limit = rest.len() as u64;
+        if read_record(&mut rest, limit)?.is_some() {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Fill `buf` from `reader`, or return false if the data runs out first.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    match reader.read_exact(buf) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Read the records in the first `limit` bytes of the segment at `path`,
+/// stopping early at a damaged one. Return how many there were, and where
+/// the last one ends.
+fn scan(path: &Path, limit: u64) -> io::Result<(u64, u64)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = 0;
+    let mut offset = 0;
+    while let Some(payload) = read_record(&mut reader, limit - offset)? {
+        records += 1;
+        offset += HEADER_LEN + payload.len() as u64;
+    }
+    Ok((records, offset))
+}
+
+/// Make sure changes to `dir`'s entries, like new or renamed files, are on
+/// disk.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+fn segment_files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".log"))
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_reopen() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::with_segment_size(dir.path(), 64).unwrap();
+    for i in 0..100 {
+        q.push(&format!("job {}", i)).unwrap();
+    }
+    assert!(segment_files(dir.path()).len() > 10);
+    for i in 0..30 {
+        assert_eq!(q.pop().unwrap(), Some(format!("job {}", i)));
+    }
+    drop(q);
+
+    let mut q: DiskQueue<String> = DiskQueue::with_segment_size(dir.path(), 64).unwrap();
+    assert_eq!(q.len(), 70);
+    assert_eq!(q.peek().unwrap().as_deref(), Some("job 30"));
+    q.push(&"job 100".to_string()).unwrap();
+    for i in 30..=100 {
+        assert_eq!(q.pop().unwrap(), Some(format!("job {}", i)));
+    }
+    assert_eq!(q.pop().unwrap(), None);
+    assert!(q.is_empty());
+}
+
+#[test]
+fn test_exactly_once() {
+    // Reopen the queue after every operation, as if each were followed by a
+    // crash, and check that each value comes out once, in order.
+    let dir = tempfile::tempdir().unwrap();
+    let open = || DiskQueue::<(u32, String)>::with_segment_size(dir.path(), 100).unwrap();
+    let mut received = Vec::new();
+    for i in 0..50 {
+        open().push(&(i, "x".repeat(i as usize))).unwrap();
+        if i % 3 == 0 {
+            received.extend(open().pop().unwrap());
+        }
+    }
+    while let Some(value) = open().pop().unwrap() {
+        received.push(value);
+    }
+    let expected: Vec<_> = (0..50).map(|i| (i, "x".repeat(i as usize))).collect();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn test_compaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::with_segment_size(dir.path(), 100).unwrap();
+    for i in 0_u64..200 {
+        q.push(&i).unwrap();
+    }
+    let segments = segment_files(dir.path()).len();
+    for i in 0..100 {
+        assert_eq!(q.pop().unwrap(), Some(i));
+    }
+    let remaining = segment_files(dir.path()).len();
+    assert!(remaining <= segments / 2 + 1);
+
+    while q.pop().unwrap().is_some() {}
+    assert_eq!(segment_files(dir.path()).len(), 1);
+
+    // A segment that fills up after everything in it was popped is deleted
+    // as soon as the next one starts.
+    let before = segment_files(dir.path());
+    for i in 0..10 {
+        q.push(&i).unwrap();
+        assert_eq!(q.pop().unwrap(), Some(i));
+        assert_eq!(segment_files(dir.path()).len(), 1);
+    }
+    assert_ne!(segment_files(dir.path()), before);
+}
+
+#[test]
+fn test_torn_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::open(dir.path()).unwrap();
+    for word in &["alpha", "beta", "gamma"] {
+        q.push(&word.to_string()).unwrap();
+    }
+    assert_eq!(q.pop().unwrap().as_deref(), Some("alpha"));
+    drop(q);
+
+    // Simulate a crash partway through writing a fourth record.
+    let path = dir.path().join(&segment_files(dir.path())[0]);
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(&[20, 0, 0, 0, 1, 2, 3, 4, b'd', b'e']).unwrap();
+    drop(file);
+
+    let mut q: DiskQueue<String> = DiskQueue::open(dir.path()).unwrap();
+    assert_eq!(q.len(), 2);
+    q.push(&"delta".to_string()).unwrap();
+    let rest: Vec<String> = std::iter::from_fn(|| q.pop().unwrap()).collect();
+    assert_eq!(rest, vec!["beta", "gamma", "delta"]);
+}
+
+#[test]
+fn test_zero_filled_tail() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::open(dir.path()).unwrap();
+    for i in 0_u64..3 {
+        q.push(&i).unwrap();
+    }
+    drop(q);
+
+    // Simulate a crash that extended the file without writing its data, with
+    // and without part of a record before the zeros.
+    let path = dir.path().join(&segment_files(dir.path())[0]);
+    let len = fs::metadata(&path).unwrap().len();
+    for garbage in &[&[][..], &[8, 0, 0, 0, 1, 2][..]] {
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(garbage).unwrap();
+        file.write_all(&[0; 24]).unwrap();
+        drop(file);
+
+        let q: DiskQueue<u64> = DiskQueue::open(dir.path()).unwrap();
+        assert_eq!(q.len(), 3);
+        assert_eq!(fs::metadata(&path).unwrap().len(), len);
+    }
+
+    let mut q: DiskQueue<u64> = DiskQueue::open(dir.path()).unwrap();
+    q.push(&3).unwrap();
+    let rest: Vec<u64> = std::iter::from_fn(|| q.pop().unwrap()).collect();
+    assert_eq!(rest, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_interrupted_compaction() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::with_segment_size(dir.path(), 32).unwrap();
+    for i in 0_u64..10 {
+        q.push(&i).unwrap();
+    }
+    let first = segment_files(dir.path())[0].clone();
+    let contents = fs::read(dir.path().join(&first)).unwrap();
+    for i in 0..5 {
+        assert_eq!(q.pop().unwrap(), Some(i));
+    }
+    drop(q);
+
+    // Put back a segment that was consumed, as if the crash came before it
+    // could be deleted.
+    assert!(!segment_files(dir.path()).contains(&first));
+    fs::write(dir.path().join(&first), contents).unwrap();
+    let mut q: DiskQueue<u64> = DiskQueue::with_segment_size(dir.path(), 32).unwrap();
+    assert!(!segment_files(dir.path()).contains(&first));
+    assert_eq!(q.len(), 5);
+    assert_eq!(q.pop().unwrap(), Some(5));
+}
+
+#[test]
+fn test_failed_pop_keeps_value() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::open(dir.path()).unwrap();
+    for i in 0_u32..3 {
+        q.push(&i).unwrap();
+    }
+    assert_eq!(q.pop().unwrap(), Some(0));
+
+    // A directory in the way of the new checkpoint makes writing it fail.
+    let obstacle = dir.path().join("checkpoint.tmp");
+    fs::create_dir(&obstacle).unwrap();
+    assert!(q.pop().is_err());
+    assert_eq!(q.len(), 2);
+    fs::remove_dir(&obstacle).unwrap();
+
+    assert_eq!(q.pop().unwrap(), Some(1));
+    drop(q);
+    let mut q: DiskQueue<u32> = DiskQueue::open(dir.path()).unwrap();
+    assert_eq!(q.pop().unwrap(), Some(2));
+    assert_eq!(q.pop().unwrap(), None);
+}
+
+#[test]
+fn test_damaged_middle_record() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut q = DiskQueue::open(dir.path()).unwrap();
+    for i in 0_u64..5 {
+        q.push(&i).unwrap();
+    }
+    drop(q);
+
+    // Flip a bit in the third record's payload. The records after it were
+    // synced, so recovery mustn't quietly truncate them away.
+    let path = dir.path().join(&segment_files(dir.path())[0]);
+    let mut contents = fs::read(&path).unwrap();
+    contents[2 * 16 + HEADER_LEN as usize] ^= 1;
+    fs::write(&path, &contents).unwrap();
+
+    let err = DiskQueue::<u64>::open(dir.path()).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(fs::metadata(&path).unwrap().len(), contents.len() as u64);
+}
@@ -1,6 +1,34 @@
 #![warn(rust_2018_idioms)]
 #![allow(elided_lifetimes_in_paths)]
 
+use std::fmt;
+use std::io;
+use std::iter::{Chain, FromIterator, Rev};
+
+pub mod async_queue;
+mod disk;
+mod persistent;
+mod priority;
+mod ring;
+pub mod spsc;
+pub mod sync;
+
+pub use disk::DiskQueue;
+pub use persistent::PersistentQueue;
+pub use priority::{PriorityHandle, PriorityQueue};
+pub use ring::{FullPolicy, RingQueue};
+
+/// A first-in, first-out queue, kept as two stacks: `older` holds the front of
+/// the queue with the oldest element last, and `younger` holds the back, with
+/// the youngest element last. Iterating in queue order means walking `older`
+/// backwards and then `younger` forwards.
+///
+/// Elements can also be pushed onto the front and popped off the back, making
+/// this a double-ended queue. When either end runs dry, the nearer half of the
+/// other stack is moved over, so each element moved has at least as many
+/// elements behind it that must be popped before that side runs dry again.
+/// That keeps every operation amortized O(1), even when alternating ends.
+#[derive(Clone)]
 pub struct Queue<T> {
     older: Vec<T>,
     younger: Vec<T>
@@ -19,18 +47,47 @@ impl<T> Queue<T> {
         self.older.is_empty() && self.younger.is_empty()
     }
 
+    pub fn len(&self) -> usize {
+        self.older.len() + self.younger.len()
+    }
+
+    /// Return a reference to the element `pop` would return next, without
+    /// removing it.
+    pub fn peek(&self) -> Option<&T> {
+        self.older.last().or_else(|| self.younger.first())
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        match self.older.last_mut() {
+            Some(front) => Some(front),
+            None => self.younger.first_mut(),
+        }
+    }
+
+    /// Return an iterator over the queue's elements, oldest first.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { inner: self.older.iter().rev().chain(self.younger.iter()) }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { inner: self.older.iter_mut().rev().chain(self.younger.iter_mut()) }
+    }
+
+    /// Add `t` to the front of the queue, so that it's the next to be popped.
+    pub fn push_front(&mut self, t: T) {
+        self.older.push(t);
+    }
+
     pub fn pop(&mut self) -> Option<T> {
         if self.older.is_empty() {
-            use std::mem::swap;
-
             if self.younger.is_empty() {
                 return None;
             }
 
-            // Bring the elements in younger over to older, and put them in
-            // the promised order.
-            swap(&mut self.older, &mut self.younger);
-            self.older.reverse();
+            // Bring the older half of the elements in younger over to older,
+            // and put them in the promised order.
+            let half = self.younger.len().div_ceil(2);
+            self.older.extend(self.younger.drain(..half).rev());
         }
 
         // Now older is guaranteed to have something. Vec's pop method
@@ -38,11 +95,155 @@ impl<T> Queue<T> {
         self.older.pop()
     }
 
+    /// Remove the youngest element from the back of the queue.
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.younger.is_empty() {
+            if self.older.is_empty() {
+                return None;
+            }
+
+            // The youngest elements in older are at its start.
+            let half = self.older.len().div_ceil(2);
+            self.younger.extend(self.older.drain(..half).rev());
+        }
+        self.younger.pop()
+    }
+
     pub fn split(self) -> (Vec<T>, Vec<T>) {
         (self.older, self.younger)
     }
 }
 
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Queue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Queue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// Queues are equal if they hold equal elements in the same order, however
+/// those elements happen to be divided between `older` and `younger`.
+impl<T: PartialEq> PartialEq for Queue<T> {
+    fn eq(&self, other: &Queue<T>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for Queue<T> {}
+
+impl<T> Extend<T> for Queue<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.younger.extend(iter);
+    }
+}
+
+impl<T> FromIterator<T> for Queue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Queue { older: Vec::new(), younger: iter.into_iter().collect() }
+    }
+}
+
+/// A queue of bytes is an in-memory pipe: writes push onto the back, and
+/// reads pop from the front. Reading an empty queue returns 0, like reading
+/// at end of file; wrap the queue in a `BufReader` for `read_line` and
+/// friends.
+impl io::Write for Queue<u8> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.younger.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl io::Read for Queue<u8> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut n = 0;
+        while n < buf.len() {
+            match self.pop() {
+                Some(byte) => buf[n] = byte,
+                None => break,
+            }
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+/// An iterator over references to a queue's elements, oldest first.
+pub struct Iter<'a, T> {
+    inner: Chain<Rev<std::slice::Iter<'a, T>>, std::slice::Iter<'a, T>>,
+}
+
+/// An iterator over mutable references to a queue's elements, oldest first.
+pub struct IterMut<'a, T> {
+    inner: Chain<Rev<std::slice::IterMut<'a, T>>, std::slice::IterMut<'a, T>>,
+}
+
+/// An iterator that moves elements out of a queue, oldest first.
+pub struct IntoIter<T> {
+    inner: Chain<Rev<std::vec::IntoIter<T>>, std::vec::IntoIter<T>>,
+}
+
+macro_rules! delegate_iterator {
+    ($name:ident < $($param:tt),* >, $item:ty) => {
+        impl<$($param),*> Iterator for $name<$($param),*> {
+            type Item = $item;
+            fn next(&mut self) -> Option<$item> {
+                self.inner.next()
+            }
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                self.inner.size_hint()
+            }
+        }
+
+        impl<$($param),*> DoubleEndedIterator for $name<$($param),*> {
+            fn next_back(&mut self) -> Option<$item> {
+                self.inner.next_back()
+            }
+        }
+
+        // `Chain` isn't `ExactSizeIterator`, since the sum of two lengths
+        // could overflow, but ours can't: both halves are in memory at once.
+        impl<$($param),*> ExactSizeIterator for $name<$($param),*> {}
+    }
+}
+
+delegate_iterator!(Iter<'a, T>, &'a T);
+delegate_iterator!(IterMut<'a, T>, &'a mut T);
+delegate_iterator!(IntoIter<T>, T);
+
+impl<T> IntoIterator for Queue<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { inner: self.older.into_iter().rev().chain(self.younger) }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Queue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Queue<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
 #[test]
 fn test() {
     let mut q = Queue::new();
@@ -73,10 +274,12 @@ fn test() {
     assert_eq!(q.pop(), Some('P'));
     q.push('X');
 
-    assert_eq!(q.split(), (vec!['D'], vec!['X']));
+    assert_eq!(q.split(), (vec![], vec!['D', 'X']));
 }
 
 #[test]
+// Borrows a queue without using the borrow, just to show that the type checks.
+#[allow(unused_must_use, clippy::no_effect)]
 fn test_generic() {
     let mut q = Queue::<char>::new();
     &mut q;
@@ -91,3 +294,153 @@ fn test_generic() {
     q.push("BTC");   // Bitcoins per USD, 2019-6
     r.push(13764.0); // Rust fails to detect irrational exuberance
 }
+
+#[cfg(test)]
+fn straddling() -> Queue<i32> {
+    // Leave elements in both `older` and `younger`.
+    let mut q: Queue<i32> = (4..7).collect();
+    q.push_front(3);
+    q.push_front(2);
+    q.push_front(1);
+    assert_eq!(q.split_ref(), (&[3, 2, 1][..], &[4, 5, 6][..]));
+    q
+}
+
+#[cfg(test)]
+impl<T> Queue<T> {
+    fn split_ref(&self) -> (&[T], &[T]) {
+        (&self.older, &self.younger)
+    }
+}
+
+#[test]
+fn test_len_peek() {
+    let mut q = Queue::new();
+    assert_eq!(q.len(), 0);
+    assert_eq!(q.peek(), None);
+    q.push('a');
+    q.push('b');
+    assert_eq!(q.peek(), Some(&'a'));
+    assert_eq!(q.len(), 2);
+
+    let mut q = straddling();
+    assert_eq!(q.len(), 6);
+    assert_eq!(q.peek(), Some(&1));
+    *q.peek_mut().unwrap() = 10;
+    assert_eq!(q.pop(), Some(10));
+    q.pop();
+    q.pop();
+    assert_eq!(q.peek(), Some(&4));
+    *q.peek_mut().unwrap() *= 100;
+    assert_eq!(q.pop(), Some(400));
+}
+
+#[test]
+fn test_iter() {
+    let mut q = straddling();
+    assert_eq!(q.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(q.iter().rev().copied().collect::<Vec<_>>(), vec![6, 5, 4, 3, 2, 1]);
+    assert_eq!(q.iter().len(), 6);
+
+    for x in &mut q {
+        *x *= 10;
+    }
+    // Iterating didn't move anything between the two vectors.
+    assert_eq!(q.split_ref(), (&[30, 20, 10][..], &[40, 50, 60][..]));
+
+    let mut sum = 0;
+    for x in &q {
+        sum += x;
+    }
+    assert_eq!(sum, 210);
+    assert_eq!(q.into_iter().collect::<Vec<_>>(), vec![10, 20, 30, 40, 50, 60]);
+}
+
+#[test]
+fn test_traits() {
+    let q = straddling();
+    let r: Queue<i32> = (1..7).collect();
+    assert_eq!(q, r);
+    assert_ne!(q, (1..6).collect());
+    assert_eq!(format!("{:?}", q), "[1, 2, 3, 4, 5, 6]");
+
+    let mut c = q.clone();
+    assert_eq!(c.pop(), Some(1));
+    assert_eq!(q.len(), 6);
+
+    let d: Queue<String> = Default::default();
+    assert!(d.is_empty());
+}
+
+#[test]
+fn test_deque() {
+    let mut q = Queue::new();
+    q.push(2);
+    q.push_front(1);
+    q.push(3);
+    q.push_front(0);
+    assert_eq!(q.iter().copied().collect::<Vec<_>>(), vec![0, 1, 2, 3]);
+    assert_eq!(q.pop_back(), Some(3));
+    assert_eq!(q.pop_back(), Some(2));
+    assert_eq!(q.pop_back(), Some(1));
+    assert_eq!(q.pop(), Some(0));
+    assert_eq!(q.pop_back(), None);
+    assert_eq!(q.pop(), None);
+
+    // Popping the back of a queue that's all in `older` moves half over.
+    let mut q: Queue<i32> = (0..10).collect();
+    assert_eq!(q.pop(), Some(0));
+    assert_eq!(q.pop_back(), Some(9));
+    assert_eq!(q.split_ref(), (&[4, 3, 2, 1][..], &[5, 6, 7, 8][..]));
+}
+
+#[test]
+fn test_alternating_ends() {
+    // Count the elements moved between the stacks, to check that popping
+    // alternate ends doesn't shuttle everything back and forth each time.
+    let n = 10_000;
+    let mut q: Queue<usize> = (0..n).collect();
+    let mut moved = 0;
+    let mut expected_front = 0;
+    let mut expected_back = n;
+    for i in 0..n {
+        let before = q.split_ref().0.len();
+        if i % 2 == 0 {
+            assert_eq!(q.pop(), Some(expected_front));
+            expected_front += 1;
+            moved += q.split_ref().0.len() + 1 - before;
+        } else {
+            expected_back -= 1;
+            assert_eq!(q.pop_back(), Some(expected_back));
+            moved += before - q.split_ref().0.len();
+        }
+    }
+    assert!(q.is_empty());
+    assert!(moved <= 2 * n, "moved {} elements", moved);
+}
+
+#[test]
+fn test_byte_pipe() {
+    use std::io::{BufRead, Read, Write};
+
+    let mut q = Queue::new();
+    writeln!(q, "GET / HTTP/1.1").unwrap();
+    q.write_all(b"Host: example.com\r\n\r\n").unwrap();
+    assert_eq!(q.len(), 36);
+
+    let mut byte = [0];
+    q.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b"G");
+    let mut lines = io::BufReader::new(&mut q).lines();
+    assert_eq!(lines.next().unwrap().unwrap(), "ET / HTTP/1.1");
+    assert_eq!(lines.next().unwrap().unwrap(), "Host: example.com");
+    assert_eq!(lines.next().unwrap().unwrap(), "");
+    assert!(lines.next().is_none());
+    assert!(q.is_empty());
+
+    io::copy(&mut &b"through the pipe"[..], &mut q).unwrap();
+    let mut text = String::new();
+    q.read_to_string(&mut text).unwrap();
+    assert_eq!(text, "through the pipe");
+    assert_eq!(q.read(&mut [0; 8]).unwrap(), 0);
+}
@@ -0,0 +1,282 @@
+//! A queue whose old versions remain valid after it's changed.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::rc::Rc;
+
+/// An immutable first-in, first-out queue. `push` and `pop` leave the queue
+/// alone and return a new version, which shares most of its structure with
+/// the old one, so keeping old versions around as snapshots is cheap.
+///
+/// As with `Queue`, the elements live on two stacks: `pop` takes from the
+/// front stack, and `push` adds to the back stack. Here the stacks are linked
+/// lists of reference-counted nodes, so two versions can share a common tail.
+/// Whenever the front stack runs out, the back stack is reversed to become the
+/// new front. Each element is reversed at most once in any single line of
+/// versions, so `push` and `pop` take constant amortized time; but popping
+/// repeatedly from the same old version repeats its reversal each time.
+pub struct PersistentQueue<T> {
+    /// The oldest elements, oldest on top. This is empty only if the whole
+    /// queue is.
+    front: Stack<T>,
+    /// The youngest elements, youngest on top.
+    back: Stack<T>,
+    len: usize,
+}
+
+/// A persistent stack: `None` when empty.
+struct Stack<T>(Option<Rc<Node<T>>>);
+
+/// The values are `Rc`s too, so that reversing a stack shared with other
+/// versions can build new nodes without cloning the values themselves.
+struct Node<T> {
+    value: Rc<T>,
+    next: Stack<T>,
+}
+
+impl<T> Stack<T> {
+    fn empty() -> Stack<T> {
+        Stack(None)
+    }
+
+    fn push(&self, value: Rc<T>) -> Stack<T> {
+        Stack(Some(Rc::new(Node { value, next: self.clone() })))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn iter(&self) -> StackIter<'_, T> {
+        StackIter(self.0.as_deref())
+    }
+
+    fn reversed(&self) -> Stack<T> {
+        let mut reversed = Stack::empty();
+        let mut node = self.0.as_deref();
+        while let Some(n) = node {
+            reversed = reversed.push(n.value.clone());
+            node = n.next.0.as_deref();
+        }
+        reversed
+    }
+}
+
+impl<T> Clone for Stack<T> {
+    fn clone(&self) -> Self {
+        Stack(self.0.clone())
+    }
+}
+
+/// Dropping a long list recursively could overflow the stack, so unlink the
+/// nodes we hold the only reference to one at a time.
+impl<T> Drop for Stack<T> {
+    fn drop(&mut self) {
+        let mut link = self.0.take();
+        while let Some(rc) = link {
+            match Rc::try_unwrap(rc) {
+                Ok(mut node) => link = node.next.0.take(),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+struct StackIter<'a, T>(Option<&'a Node<T>>);
+
+impl<'a, T> Iterator for StackIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.0?;
+        self.0 = node.next.0.as_deref();
+        Some(&node.value)
+    }
+}
+
+impl<T> PersistentQueue<T> {
+    pub fn new() -> PersistentQueue<T> {
+        PersistentQueue { front: Stack::empty(), back: Stack::empty(), len: 0 }
+    }
+
+    /// Build a queue from two stacks, restoring the invariant that the front
+    /// is empty only if the back is too.
+    fn from_stacks(front: Stack<T>, back: Stack<T>, len: usize) -> PersistentQueue<T> {
+        if front.is_empty() {
+            PersistentQueue { front: back.reversed(), back: Stack::empty(), len }
+        } else {
+            PersistentQueue { front, back, len }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return a new version of the queue with `t` added at the back.
+    pub fn push(&self, t: T) -> PersistentQueue<T> {
+        PersistentQueue::from_stacks(self.front.clone(), self.back.push(Rc::new(t)), self.len + 1)
+    }
+
+    /// Return the oldest element, and a new version of the queue without it.
+    pub fn pop(&self) -> Option<(&T, PersistentQueue<T>)> {
+        let node = self.front.0.as_deref()?;
+        let rest = PersistentQueue::from_stacks(node.next.clone(), self.back.clone(), self.len - 1);
+        Some((&node.value, rest))
+    }
+
+    /// Return the element `pop` would return next.
+    pub fn peek(&self) -> Option<&T> {
+        self.front.iter().next()
+    }
+
+    /// Return an iterator over the queue's elements, oldest first.
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut back: Vec<&T> = self.back.iter().collect();
+        back.reverse();
+        Iter { front: self.front.iter(), back: back.into_iter() }
+    }
+}
+
+/// Cloning a `PersistentQueue` takes constant time, since the clone shares
+/// all its structure.
+impl<T> Clone for PersistentQueue<T> {
+    fn clone(&self) -> Self {
+        PersistentQueue { front: self.front.clone(), back: self.back.clone(), len: self.len }
+    }
+}
+
+impl<T> Default for PersistentQueue<T> {
+    fn default() -> Self {
+        PersistentQueue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for PersistentQueue<T> {
+    fn eq(&self, other: &PersistentQueue<T>) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for PersistentQueue<T> {}
+
+impl<T> FromIterator<T> for PersistentQueue<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        iter.into_iter().fold(PersistentQueue::new(), |q, t| q.push(t))
+    }
+}
+
+/// An iterator over a `PersistentQueue`'s elements, oldest first.
+pub struct Iter<'a, T> {
+    front: StackIter<'a, T>,
+    back: std::vec::IntoIter<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.front.next().or_else(|| self.back.next())
+    }
+}
+
+impl<'a, T> IntoIterator for &'a PersistentQueue<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[test]
+fn test_push_pop() {
+    let empty = PersistentQueue::new();
+    let q = empty.push('a').push('b').push('c');
+    assert_eq!(q.len(), 3);
+    assert_eq!(q.peek(), Some(&'a'));
+
+    let (first, q) = q.pop().unwrap();
+    assert_eq!(*first, 'a');
+    let q = q.push('d');
+    let mut popped = Vec::new();
+    let mut rest = q.clone();
+    while let Some((t, next)) = rest.pop() {
+        popped.push(*t);
+        rest = next;
+    }
+    assert_eq!(popped, vec!['b', 'c', 'd']);
+    assert!(rest.is_empty());
+    assert!(empty.pop().is_none());
+}
+
+#[test]
+fn test_old_versions_unchanged() {
+    // Keep every version of a queue as it's built up and torn down, and check
+    // that none of them is disturbed by the versions derived from it.
+    let mut versions = vec![PersistentQueue::new()];
+    for i in 0..10 {
+        let next = versions.last().unwrap().push(i);
+        versions.push(next);
+        if i % 3 == 2 {
+            let (_, next) = versions.last().unwrap().pop().unwrap();
+            versions.push(next);
+        }
+    }
+
+    let mut expected: Vec<Vec<i32>> = vec![vec![]];
+    for i in 0..10 {
+        let mut next = expected.last().unwrap().clone();
+        next.push(i);
+        expected.push(next);
+        if i % 3 == 2 {
+            let next = expected.last().unwrap()[1..].to_vec();
+            expected.push(next);
+        }
+    }
+
+    for (version, expected) in versions.iter().zip(&expected) {
+        assert_eq!(version.iter().copied().collect::<Vec<_>>(), *expected);
+        assert_eq!(version.len(), expected.len());
+    }
+
+    // Branch two different futures off the same snapshot.
+    let snapshot = &versions[5];
+    let left = snapshot.push(100);
+    let right = snapshot.pop().unwrap().1.push(200);
+    assert_eq!(format!("{:?}", snapshot), "[1, 2, 3]");
+    assert_eq!(format!("{:?}", left), "[1, 2, 3, 100]");
+    assert_eq!(format!("{:?}", right), "[2, 3, 200]");
+}
+
+#[test]
+fn test_sharing() {
+    // Values aren't cloned, even when a shared back stack is reversed.
+    let marker = Rc::new(());
+    let q: PersistentQueue<Rc<()>> = (0..5).map(|_| marker.clone()).collect();
+    let snapshot = q.clone();
+    let rest = q.pop().unwrap().1.push(marker.clone());
+    assert_eq!(Rc::strong_count(&marker), 7);
+    assert_eq!(rest.len(), 5);
+    assert_eq!(snapshot, q);
+    drop((q, snapshot, rest));
+    assert_eq!(Rc::strong_count(&marker), 1);
+}
+
+#[test]
+fn test_long_queue_drop() {
+    let q: PersistentQueue<u32> = (0..1_000_000).collect();
+    let (_, rest) = q.pop().unwrap();
+    drop(q);
+    assert_eq!(rest.len(), 999_999);
+}
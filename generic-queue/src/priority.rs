@@ -0,0 +1,265 @@
+//! A priority queue that is first-in, first-out among equal priorities.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifies an element pushed onto a `PriorityQueue`, so that its priority
+/// can be changed later. Handles are only meaningful to the queue that
+/// issued them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PriorityHandle(u64);
+
+/// A queue whose `pop` returns the element with the highest priority, and,
+/// among elements of equal priority, the one pushed first.
+///
+/// `std::collections::BinaryHeap` makes no promises about the order of equal
+/// elements. This is a binary heap too, but each entry carries a sequence
+/// number recording when it was pushed, which breaks ties. The heap also
+/// tracks where each entry sits, so `change_priority` and `remove` can find
+/// an element by its handle in constant time, and then restore the heap in
+/// `O(log n)`.
+pub struct PriorityQueue<P, T> {
+    heap: Vec<Entry<P, T>>,
+    /// The index in `heap` of each entry, by sequence number.
+    positions: HashMap<u64, usize>,
+    next_sequence: u64,
+}
+
+struct Entry<P, T> {
+    priority: P,
+    sequence: u64,
+    value: T,
+}
+
+impl<P: Ord, T> Entry<P, T> {
+    /// Entries that should be popped first are greater.
+    fn cmp(&self, other: &Entry<P, T>) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl<P: Ord, T> PriorityQueue<P, T> {
+    pub fn new() -> PriorityQueue<P, T> {
+        PriorityQueue { heap: Vec::new(), positions: HashMap::new(), next_sequence: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Add `value` to the queue with the given priority, and return a handle
+    /// for it.
+    pub fn push(&mut self, priority: P, value: T) -> PriorityHandle {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        let index = self.heap.len();
+        self.heap.push(Entry { priority, sequence, value });
+        self.positions.insert(sequence, index);
+        self.sift_up(index);
+        PriorityHandle(sequence)
+    }
+
+    /// Remove the element with the highest priority, the oldest first among
+    /// equals, and return it with its priority.
+    pub fn pop(&mut self) -> Option<(P, T)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        Some(self.remove_at(0))
+    }
+
+    /// Return the element `pop` would remove next, with its priority.
+    pub fn peek(&self) -> Option<(&P, &T)> {
+        self.heap.first().map(|entry| (&entry.priority, &entry.value))
+    }
+
+    /// Return true if the element identified by `handle` is still queued.
+    pub fn contains(&self, handle: PriorityHandle) -> bool {
+        self.positions.contains_key(&handle.0)
+    }
+
+    /// Return the element identified by `handle`, with its priority, if it's
+    /// still queued.
+    pub fn get(&self, handle: PriorityHandle) -> Option<(&P, &T)> {
+        let entry = &self.heap[*self.positions.get(&handle.0)?];
+        Some((&entry.priority, &entry.value))
+    }
+
+    /// Give the element identified by `handle` a new priority, and return its
+    /// old one, or `None` if it's no longer queued. The element keeps its
+    /// place in line among elements of its new priority, according to when it
+    /// was first pushed.
+    pub fn change_priority(&mut self, handle: PriorityHandle, priority: P) -> Option<P> {
+        let index = *self.positions.get(&handle.0)?;
+        let old = std::mem::replace(&mut self.heap[index].priority, priority);
+        let index = self.sift_up(index);
+        self.sift_down(index);
+        Some(old)
+    }
+
+    /// Remove the element identified by `handle`, and return it with its
+    /// priority, or `None` if it's no longer queued.
+    pub fn remove(&mut self, handle: PriorityHandle) -> Option<(P, T)> {
+        let index = *self.positions.get(&handle.0)?;
+        Some(self.remove_at(index))
+    }
+
+    fn remove_at(&mut self, index: usize) -> (P, T) {
+        let last = self.heap.len() - 1;
+        self.swap(index, last);
+        let entry = self.heap.pop().unwrap();
+        self.positions.remove(&entry.sequence);
+        if index < self.heap.len() {
+            let index = self.sift_up(index);
+            self.sift_down(index);
+        }
+        (entry.priority, entry.value)
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions.insert(self.heap[a].sequence, a);
+        self.positions.insert(self.heap[b].sequence, b);
+    }
+
+    /// Move the entry at `index` up until its parent is greater, and return
+    /// where it ends up.
+    fn sift_up(&mut self, mut index: usize) -> usize {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if self.heap[index].cmp(&self.heap[parent]) != Ordering::Greater {
+                break;
+            }
+            self.swap(index, parent);
+            index = parent;
+        }
+        index
+    }
+
+    /// Move the entry at `index` down until both its children are less.
+    fn sift_down(&mut self, mut index: usize) {
+        loop {
+            let mut greatest = index;
+            for child in [2 * index + 1, 2 * index + 2].iter().copied() {
+                if child < self.heap.len()
+                    && self.heap[child].cmp(&self.heap[greatest]) == Ordering::Greater
+                {
+                    greatest = child;
+                }
+            }
+            if greatest == index {
+                return;
+            }
+            self.swap(index, greatest);
+            index = greatest;
+        }
+    }
+}
+
+impl<P: Ord, T> Default for PriorityQueue<P, T> {
+    fn default() -> Self {
+        PriorityQueue::new()
+    }
+}
+
+impl<P: fmt::Debug, T: fmt::Debug> fmt::Debug for PriorityQueue<P, T> {
+    /// Show the entries in heap order, which is not the order `pop` would
+    /// produce them.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.heap.iter().map(|entry| (&entry.priority, &entry.value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+impl<P: Ord, T> PriorityQueue<P, T> {
+    /// Check the heap property and the position index.
+    fn check(&self) {
+        for (index, entry) in self.heap.iter().enumerate() {
+            assert_eq!(self.positions[&entry.sequence], index);
+            if index > 0 {
+                assert_ne!(entry.cmp(&self.heap[(index - 1) / 2]), Ordering::Greater);
+            }
+        }
+        assert_eq!(self.positions.len(), self.heap.len());
+    }
+}
+
+#[test]
+fn test_fifo_among_equals() {
+    let mut q = PriorityQueue::new();
+    for (priority, name) in [(1, "a"), (2, "b"), (1, "c"), (3, "d"), (2, "e"), (1, "f"),
+                             (2, "g"), (1, "h")].iter().copied() {
+        q.push(priority, name);
+        q.check();
+    }
+    assert_eq!(q.peek(), Some((&3, &"d")));
+    let mut order = Vec::new();
+    while let Some((_, name)) = q.pop() {
+        q.check();
+        order.push(name);
+    }
+    assert_eq!(order, vec!["d", "b", "e", "g", "a", "c", "f", "h"]);
+}
+
+#[test]
+fn test_many_equal_priorities() {
+    // With only a few distinct priorities, a plain binary heap would scramble
+    // each priority's elements.
+    let mut q = PriorityQueue::new();
+    for i in 0..1000 {
+        q.push(i % 3, i);
+    }
+    let popped: Vec<(i32, i32)> = std::iter::from_fn(|| q.pop()).collect();
+    let mut expected: Vec<(i32, i32)> = (0..1000).map(|i| (i % 3, i)).collect();
+    expected.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    assert_eq!(popped, expected);
+}
+
+#[test]
+fn test_change_priority() {
+    let mut q = PriorityQueue::new();
+    let a = q.push(5, 'a');
+    let b = q.push(5, 'b');
+    let c = q.push(1, 'c');
+    let d = q.push(1, 'd');
+
+    assert_eq!(q.change_priority(d, 9), Some(1));
+    q.check();
+    assert_eq!(q.get(d), Some((&9, &'d')));
+    assert_eq!(q.change_priority(a, 0), Some(5));
+    q.check();
+
+    // 'c' joins priority 5 behind 'b', which was pushed before it.
+    assert_eq!(q.change_priority(c, 5), Some(1));
+    assert_eq!(q.pop(), Some((9, 'd')));
+    assert_eq!(q.pop(), Some((5, 'b')));
+    assert_eq!(q.pop(), Some((5, 'c')));
+    assert!(!q.contains(b));
+    assert_eq!(q.change_priority(b, 7), None);
+    assert_eq!(q.pop(), Some((0, 'a')));
+    assert_eq!(q.pop(), None);
+}
+
+#[test]
+fn test_remove() {
+    let mut q = PriorityQueue::new();
+    let handles: Vec<_> = (0..20).map(|i| q.push(i % 5, i)).collect();
+    for handle in handles.iter().step_by(3) {
+        assert!(q.remove(*handle).is_some());
+        q.check();
+    }
+    assert_eq!(q.remove(handles[0]), None);
+    assert_eq!(q.len(), 13);
+
+    let popped: Vec<i32> = std::iter::from_fn(|| q.pop()).map(|(_, i)| i).collect();
+    assert_eq!(popped, vec![4, 14, 19, 8, 13, 2, 7, 17, 1, 11, 16, 5, 10]);
+}
@@ -0,0 +1,207 @@
+//! A fixed-capacity queue stored in a circular buffer.
+
+use std::fmt;
+
+/// What a `RingQueue` does when something is pushed onto it while it's full.
+pub enum FullPolicy<T> {
+    /// Refuse the new element, handing it back to the caller.
+    Reject,
+    /// Drop the oldest element to make room.
+    Overwrite,
+    /// Remove the oldest element to make room, and pass it to the callback.
+    Evict(Box<dyn FnMut(T) + Send>),
+}
+
+impl<T> fmt::Debug for FullPolicy<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FullPolicy::Reject => write!(f, "Reject"),
+            FullPolicy::Overwrite => write!(f, "Overwrite"),
+            FullPolicy::Evict(_) => write!(f, "Evict(..)"),
+        }
+    }
+}
+
+/// A first-in, first-out queue holding at most a fixed number of elements.
+///
+/// Unlike `Queue`, which occasionally stops to move half its elements from one
+/// vector to the other, every operation here takes constant time: the elements
+/// live in a circular buffer allocated once, up front. What happens when the
+/// buffer is full is up to the queue's `FullPolicy`.
+pub struct RingQueue<T> {
+    /// Slot `(head + i) % capacity` holds the `i`'th oldest element, for `i` in
+    /// `0..len`; all other slots are `None`.
+    buffer: Vec<Option<T>>,
+    head: usize,
+    len: usize,
+    policy: FullPolicy<T>,
+}
+
+impl<T> RingQueue<T> {
+    /// Return a new queue holding at most `capacity` elements, treating
+    /// pushes onto a full queue as `policy` says. Panic if `capacity` is zero.
+    pub fn new(capacity: usize, policy: FullPolicy<T>) -> RingQueue<T> {
+        assert!(capacity > 0, "RingQueue capacity must be positive");
+        let mut buffer = Vec::with_capacity(capacity);
+        buffer.resize_with(capacity, || None);
+        RingQueue { buffer, head: 0, len: 0, policy }
+    }
+
+    /// Return a queue that refuses new elements when full.
+    pub fn rejecting(capacity: usize) -> RingQueue<T> {
+        RingQueue::new(capacity, FullPolicy::Reject)
+    }
+
+    /// Return a queue that drops its oldest element to make room when full.
+    pub fn overwriting(capacity: usize) -> RingQueue<T> {
+        RingQueue::new(capacity, FullPolicy::Overwrite)
+    }
+
+    /// Return a queue that passes its oldest element to `evict` to make room
+    /// when full.
+    pub fn evicting<F>(capacity: usize, evict: F) -> RingQueue<T>
+    where
+        F: FnMut(T) + Send + 'static,
+    {
+        RingQueue::new(capacity, FullPolicy::Evict(Box::new(evict)))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    /// Return the index in `buffer` of the `i`'th oldest element.
+    fn slot(&self, i: usize) -> usize {
+        (self.head + i) % self.capacity()
+    }
+
+    /// Add `t` to the back of the queue. If the queue is full, a `Reject`
+    /// policy returns `t` in an error; the others make room for it.
+    pub fn push(&mut self, t: T) -> Result<(), T> {
+        if self.is_full() {
+            if let FullPolicy::Reject = self.policy {
+                return Err(t);
+            }
+            let oldest = self.pop().unwrap();
+            if let FullPolicy::Evict(evict) = &mut self.policy {
+                evict(oldest);
+            }
+        }
+        let slot = self.slot(self.len);
+        self.buffer[slot] = Some(t);
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Remove the oldest element from the queue.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let t = self.buffer[self.head].take();
+        self.head = self.slot(1);
+        self.len -= 1;
+        t
+    }
+
+    /// Return a reference to the element `pop` would return next.
+    pub fn peek(&self) -> Option<&T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.buffer[self.head].as_ref()
+    }
+
+    /// Remove every element, without passing any to an eviction callback.
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Return an iterator over the queue's elements, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.len).map(move |i| self.buffer[self.slot(i)].as_ref().unwrap())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RingQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[test]
+fn test_policies() {
+    let mut q = RingQueue::rejecting(3);
+    for i in 0..3 {
+        q.push(i).unwrap();
+    }
+    assert!(q.is_full());
+    assert_eq!(q.push(3), Err(3));
+    assert_eq!(q.pop(), Some(0));
+    q.push(3).unwrap();
+    assert_eq!(q.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+    let mut q = RingQueue::overwriting(3);
+    for i in 0..10 {
+        q.push(i).unwrap();
+    }
+    assert_eq!(q.len(), 3);
+    assert_eq!(q.peek(), Some(&7));
+    assert_eq!(format!("{:?}", q), "[7, 8, 9]");
+
+    use std::sync::{Arc, Mutex};
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let mut q = {
+        let evicted = evicted.clone();
+        RingQueue::evicting(2, move |old| evicted.lock().unwrap().push(old))
+    };
+    for word in "the quick brown fox".split(' ') {
+        q.push(word).unwrap();
+    }
+    assert_eq!(*evicted.lock().unwrap(), vec!["the", "quick"]);
+    assert_eq!(q.pop(), Some("brown"));
+    assert_eq!(q.pop(), Some("fox"));
+    assert_eq!(q.pop(), None);
+}
+
+#[test]
+fn test_wraparound() {
+    let mut q = RingQueue::rejecting(4);
+    let mut next = 0;
+    // Keep the queue partly full while its contents travel around the buffer
+    // several times. Each round pops the next number in sequence.
+    for round in 0..50 {
+        while q.len() < 1 + round % 4 {
+            q.push(next).unwrap();
+            next += 1;
+        }
+        assert_eq!(q.pop(), Some(round));
+        assert!(q.iter().copied().eq(round + 1..next));
+    }
+    q.clear();
+    assert!(q.is_empty());
+    assert_eq!(q.peek(), None);
+}
+
+#[test]
+fn test_last_log_records() {
+    let mut recent = RingQueue::overwriting(100);
+    for i in 0..10_000 {
+        recent.push(format!("request {} handled", i)).unwrap();
+    }
+    assert_eq!(recent.len(), 100);
+    assert_eq!(recent.peek().map(String::as_str), Some("request 9900 handled"));
+    assert_eq!(recent.iter().last().map(String::as_str), Some("request 9999 handled"));
+}
@@ -0,0 +1,313 @@
+//! A wait-free queue for passing values from one thread to one other thread.
+//!
+//! `channel(capacity)` returns a `Producer` and a `Consumer` sharing a ring
+//! buffer. Neither ever blocks or takes a lock: `push` fails if the buffer is
+//! full, and `pop` returns `None` if it's empty, each after a bounded number
+//! of steps. That makes this suitable for real-time threads, like an audio
+//! callback, that must not wait on anyone else.
+
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// A value aligned to start on its own cache line, so that writes to it don't
+/// slow down reads of its neighbours on other processors. Some processors
+/// fetch cache lines in adjacent pairs, so we use twice the usual 64 bytes.
+#[repr(align(128))]
+struct CachePadded<T>(T);
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// The buffer shared by a `Producer` and `Consumer`.
+///
+/// `head` and `tail` count the values popped and pushed, modulo twice the
+/// number of slots. The values not yet popped live in the slots from `head`
+/// to `tail`, taken modulo the number of slots. Counting up to twice the
+/// capacity lets us tell a full buffer, where `tail` is `capacity` ahead of
+/// `head`, from an empty one, where they're equal. Only the consumer stores
+/// to `head`, and only the producer stores to `tail`.
+struct Buffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    head: CachePadded<AtomicUsize>,
+    tail: CachePadded<AtomicUsize>,
+}
+
+// The producer and consumer only ever touch distinct slots, and hand each
+// slot over to the other with a release store and an acquire load, so sharing
+// the buffer between them is safe as long as the values can cross threads.
+unsafe impl<T: Send> Sync for Buffer<T> {}
+
+impl<T> Buffer<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, count: usize) -> *mut MaybeUninit<T> {
+        self.slots[count % self.capacity()].get()
+    }
+
+    /// Return the count after `count`.
+    fn advance(&self, count: usize) -> usize {
+        if count + 1 == 2 * self.capacity() { 0 } else { count + 1 }
+    }
+
+    /// Return the number of values between the counts `head` and `tail`.
+    fn distance(&self, head: usize, tail: usize) -> usize {
+        if head <= tail { tail - head } else { tail + 2 * self.capacity() - head }
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        let head = self.head.load(Ordering::Relaxed);
+        let tail = self.tail.load(Ordering::Relaxed);
+        let mut count = head;
+        while count != tail {
+            unsafe {
+                // Both handles are gone, so the slots from head to tail are
+                // ours, and they hold initialized values nobody popped.
+                std::ptr::drop_in_place((*self.slot(count)).as_mut_ptr());
+            }
+            count = self.advance(count);
+        }
+    }
+}
+
+/// Return a producer and consumer sharing a queue that holds at most
+/// `capacity` values. Panic if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "spsc::channel capacity must be positive");
+    assert!(capacity <= usize::MAX / 2, "spsc::channel capacity too large");
+    let slots = (0..capacity).map(|_| UnsafeCell::new(MaybeUninit::uninit())).collect();
+    let buffer = Arc::new(Buffer {
+        slots,
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(0)),
+    });
+    let producer = Producer { buffer: buffer.clone(), tail: 0, cached_head: 0 };
+    let consumer = Consumer { buffer, head: 0, cached_tail: 0 };
+    (producer, consumer)
+}
+
+/// The sending half of a queue created by `channel`.
+pub struct Producer<T> {
+    buffer: Arc<Buffer<T>>,
+    /// Our own copy of `buffer.tail`, which only we change.
+    tail: usize,
+    /// The consumer's `head` as of the last time we looked. Since the head
+    /// only advances, this underestimates the free space, so we can go on
+    /// pushing without touching the consumer's cache line until it seems
+    /// full.
+    cached_head: usize,
+}
+
+/// The receiving half of a queue created by `channel`.
+pub struct Consumer<T> {
+    buffer: Arc<Buffer<T>>,
+    /// Our own copy of `buffer.head`, which only we change.
+    head: usize,
+    /// The producer's `tail` as of the last time we looked.
+    cached_tail: usize,
+}
+
+impl<T> Producer<T> {
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Add `value` to the back of the queue, or return it in an error if the
+    /// queue is full.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        if self.buffer.distance(self.cached_head, self.tail) == self.capacity() {
+            self.cached_head = self.buffer.head.load(Ordering::Acquire);
+            if self.buffer.distance(self.cached_head, self.tail) == self.capacity() {
+                return Err(value);
+            }
+        }
+
+        unsafe {
+            // The slot at tail is outside head..tail, so the consumer isn't
+            // looking at it, and the acquire load of head above ensures it
+            // has finished moving out any value it held before.
+            (*self.buffer.slot(self.tail)).as_mut_ptr().write(value);
+        }
+        self.tail = self.buffer.advance(self.tail);
+        self.buffer.tail.store(self.tail, Ordering::Release);
+        Ok(())
+    }
+
+    /// Return true if the queue is full. The consumer may pop at any moment,
+    /// so this is only a hint.
+    pub fn is_full(&self) -> bool {
+        let head = self.buffer.head.load(Ordering::Acquire);
+        self.buffer.distance(head, self.tail) == self.capacity()
+    }
+}
+
+impl<T> Consumer<T> {
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Remove the value at the front of the queue, or return `None` if the
+    /// queue is empty.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == self.cached_tail {
+            self.cached_tail = self.buffer.tail.load(Ordering::Acquire);
+            if self.head == self.cached_tail {
+                return None;
+            }
+        }
+
+        let value = unsafe {
+            // The slot at head is inside head..tail, and the acquire load of
+            // tail above ensures the producer's write to it is visible.
+            (*self.buffer.slot(self.head)).as_ptr().read()
+        };
+        self.head = self.buffer.advance(self.head);
+        self.buffer.head.store(self.head, Ordering::Release);
+        Some(value)
+    }
+
+    /// Return a reference to the value at the front of the queue, without
+    /// removing it.
+    pub fn peek(&mut self) -> Option<&T> {
+        if self.head == self.cached_tail {
+            self.cached_tail = self.buffer.tail.load(Ordering::Acquire);
+            if self.head == self.cached_tail {
+                return None;
+            }
+        }
+        unsafe {
+            // As for `pop`; the producer won't overwrite the slot until we
+            // advance head, which needs `&mut self`.
+            Some(&*(*self.buffer.slot(self.head)).as_ptr())
+        }
+    }
+
+    /// Return the number of values in the queue. The producer may push at any
+    /// moment, so this is only a lower bound.
+    pub fn len(&self) -> usize {
+        self.buffer.distance(self.head, self.buffer.tail.load(Ordering::Acquire))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> fmt::Debug for Producer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Producer").field("capacity", &self.capacity()).finish()
+    }
+}
+
+impl<T> fmt::Debug for Consumer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Consumer")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+#[test]
+fn test_single_thread() {
+    let (mut tx, mut rx) = channel(3);
+    assert_eq!(rx.pop(), None);
+    tx.push('a').unwrap();
+    tx.push('b').unwrap();
+    tx.push('c').unwrap();
+    assert!(tx.is_full());
+    assert_eq!(tx.push('d'), Err('d'));
+    assert_eq!(rx.len(), 3);
+    assert_eq!(rx.peek(), Some(&'a'));
+    assert_eq!(rx.pop(), Some('a'));
+    tx.push('d').unwrap();
+    assert_eq!(rx.pop(), Some('b'));
+    assert_eq!(rx.pop(), Some('c'));
+    assert_eq!(rx.pop(), Some('d'));
+    assert_eq!(rx.pop(), None);
+    assert!(rx.is_empty());
+}
+
+#[test]
+fn test_drops_leftovers() {
+    let marker = Arc::new(());
+    let (mut tx, mut rx) = channel(4);
+    for _ in 0..4 {
+        tx.push(marker.clone()).unwrap();
+    }
+    drop(rx.pop());
+    assert_eq!(Arc::strong_count(&marker), 4);
+    drop(tx);
+    drop(rx);
+    assert_eq!(Arc::strong_count(&marker), 1);
+}
+
+#[test]
+fn test_stress() {
+    // Push a long sequence through a small buffer, so that it wraps around
+    // many times and both threads keep finding it full or empty, and check
+    // that every value arrives, in order.
+    const COUNT: u64 = 1_000_000;
+    let (mut tx, mut rx) = channel(64);
+
+    let producer = std::thread::spawn(move || {
+        for i in 0..COUNT {
+            let mut value = i;
+            while let Err(rejected) = tx.push(value) {
+                value = rejected;
+                std::thread::yield_now();
+            }
+        }
+    });
+
+    let mut expected = 0;
+    while expected < COUNT {
+        match rx.pop() {
+            Some(value) => {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+            None => std::thread::yield_now(),
+        }
+    }
+    producer.join().unwrap();
+    assert_eq!(rx.pop(), None);
+}
+
+#[test]
+fn test_stress_boxed() {
+    // The same, with values that own heap memory, so that a torn or
+    // duplicated value would likely crash or leak.
+    let (mut tx, mut rx) = channel(7);
+    let producer = std::thread::spawn(move || {
+        for i in 0..100_000 {
+            let mut value = Box::new(i.to_string());
+            while let Err(rejected) = tx.push(value) {
+                value = rejected;
+                std::thread::yield_now();
+            }
+        }
+    });
+
+    for i in 0..100_000 {
+        let value = loop {
+            if let Some(value) = rx.pop() {
+                break value;
+            }
+            std::thread::yield_now();
+        };
+        assert_eq!(*value, i.to_string());
+    }
+    producer.join().unwrap();
+}
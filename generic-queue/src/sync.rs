@@ -0,0 +1,337 @@
+//! A `Queue` that threads can share, blocking when it's empty or full.
+
+use crate::Queue;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// A first-in, first-out queue for passing values between threads.
+///
+/// Popping from an empty queue blocks until a value arrives. If the queue was
+/// created with a capacity, pushing onto a full queue blocks until there's
+/// room. Closing the queue wakes everyone: pushes fail from then on, and pops
+/// drain whatever is left and then report that the queue is closed. Share a
+/// `SyncQueue` between threads with an `Arc`.
+pub struct SyncQueue<T> {
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: Option<usize>,
+}
+
+struct State<T> {
+    queue: Queue<T>,
+    closed: bool,
+}
+
+/// The error returned by `SyncQueue::push` on a closed queue, carrying the
+/// value that couldn't be pushed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Closed<T>(pub T);
+
+impl<T> fmt::Display for Closed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "push onto closed queue")
+    }
+}
+
+impl<T: fmt::Debug> Error for Closed<T> {}
+
+/// The error returned by `SyncQueue::try_pop`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TryPopError {
+    /// The queue is empty, but still open.
+    Empty,
+    /// The queue is empty and closed.
+    Closed,
+}
+
+/// The error returned by `SyncQueue::pop_timeout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PopTimeoutError {
+    /// Nothing arrived before the timeout expired.
+    Timeout,
+    /// The queue is empty and closed.
+    Closed,
+}
+
+impl fmt::Display for TryPopError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TryPopError::Empty => write!(f, "queue is empty"),
+            TryPopError::Closed => write!(f, "queue is empty and closed"),
+        }
+    }
+}
+
+impl fmt::Display for PopTimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PopTimeoutError::Timeout => write!(f, "timed out waiting on queue"),
+            PopTimeoutError::Closed => write!(f, "queue is empty and closed"),
+        }
+    }
+}
+
+impl Error for TryPopError {}
+impl Error for PopTimeoutError {}
+
+impl<T> SyncQueue<T> {
+    /// Return a new queue with no limit on its length.
+    pub fn new() -> SyncQueue<T> {
+        SyncQueue::build(None)
+    }
+
+    /// Return a new queue that holds at most `capacity` values. Panic if
+    /// `capacity` is zero.
+    pub fn with_capacity(capacity: usize) -> SyncQueue<T> {
+        assert!(capacity > 0, "SyncQueue capacity must be positive");
+        SyncQueue::build(Some(capacity))
+    }
+
+    fn build(capacity: Option<usize>) -> SyncQueue<T> {
+        SyncQueue {
+            state: Mutex::new(State { queue: Queue::new(), closed: false }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap()
+    }
+
+    fn is_full(&self, state: &State<T>) -> bool {
+        self.capacity.is_some_and(|capacity| state.queue.len() >= capacity)
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().queue.is_empty()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Add `value` to the back of the queue, waiting for room if the queue is
+    /// full. If the queue is closed, return the value in an error.
+    pub fn push(&self, value: T) -> Result<(), Closed<T>> {
+        let mut state = self.lock();
+        while !state.closed && self.is_full(&state) {
+            state = self.not_full.wait(state).unwrap();
+        }
+        if state.closed {
+            return Err(Closed(value));
+        }
+        state.queue.push(value);
+        drop(state);
+        self.not_empty.notify_one();
+        Ok(())
+    }
+
+    /// Remove the value at the front of the queue, waiting for one to arrive
+    /// if the queue is empty. Return `None` once the queue is empty and
+    /// closed.
+    pub fn pop(&self) -> Option<T> {
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.queue.pop() {
+                drop(state);
+                self.not_full.notify_one();
+                return Some(value);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self.not_empty.wait(state).unwrap();
+        }
+    }
+
+    /// Like `pop`, but give up after waiting for `timeout`. A timeout too
+    /// long to represent as a deadline means waiting indefinitely.
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<T, PopTimeoutError> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = state.queue.pop() {
+                drop(state);
+                self.not_full.notify_one();
+                return Ok(value);
+            }
+            if state.closed {
+                return Err(PopTimeoutError::Closed);
+            }
+
+            // Wakeups can be spurious, or another thread may beat us to the
+            // value, so wait only for whatever time remains.
+            state = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(PopTimeoutError::Timeout);
+                    }
+                    self.not_empty.wait_timeout(state, deadline - now).unwrap().0
+                }
+                None => self.not_empty.wait(state).unwrap(),
+            };
+        }
+    }
+
+    /// Remove the value at the front of the queue, if there is one, without
+    /// waiting.
+    pub fn try_pop(&self) -> Result<T, TryPopError> {
+        let mut state = self.lock();
+        match state.queue.pop() {
+            Some(value) => {
+                drop(state);
+                self.not_full.notify_one();
+                Ok(value)
+            }
+            None if state.closed => Err(TryPopError::Closed),
+            None => Err(TryPopError::Empty),
+        }
+    }
+
+    /// Close the queue, waking every thread waiting to push or pop. Values
+    /// already in the queue can still be popped.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+impl<T> Default for SyncQueue<T> {
+    fn default() -> Self {
+        SyncQueue::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SyncQueue<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("SyncQueue")
+            .field("queue", &state.queue)
+            .field("closed", &state.closed)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+use std::sync::Arc;
+#[cfg(test)]
+use std::thread;
+
+#[test]
+fn test_single_thread() {
+    let q = SyncQueue::new();
+    assert_eq!(q.try_pop(), Err(TryPopError::Empty));
+    q.push(1).unwrap();
+    q.push(2).unwrap();
+    assert_eq!(q.len(), 2);
+    assert_eq!(q.pop(), Some(1));
+    assert_eq!(q.pop_timeout(Duration::from_millis(1)), Ok(2));
+    assert_eq!(q.pop_timeout(Duration::from_millis(10)), Err(PopTimeoutError::Timeout));
+
+    q.push(3).unwrap();
+    q.close();
+    assert_eq!(q.push(4), Err(Closed(4)));
+    assert_eq!(q.pop(), Some(3));
+    assert_eq!(q.pop(), None);
+    assert_eq!(q.try_pop(), Err(TryPopError::Closed));
+    assert_eq!(q.pop_timeout(Duration::from_secs(60)), Err(PopTimeoutError::Closed));
+}
+
+#[test]
+fn test_worker_pool() {
+    let q = Arc::new(SyncQueue::with_capacity(4));
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let q = q.clone();
+            thread::spawn(move || {
+                let mut sum = 0_u64;
+                while let Some(n) = q.pop() {
+                    sum += n;
+                }
+                sum
+            })
+        })
+        .collect();
+
+    for n in 1..=1000 {
+        q.push(n).unwrap();
+        assert!(q.len() <= 4);
+    }
+    q.close();
+
+    let total: u64 = workers.into_iter().map(|w| w.join().unwrap()).sum();
+    assert_eq!(total, 500_500);
+}
+
+#[test]
+fn test_close_wakes_waiters() {
+    let empty = Arc::new(SyncQueue::<i32>::new());
+    let full = Arc::new(SyncQueue::<i32>::with_capacity(1));
+    full.push(0).unwrap();
+
+    let popper = {
+        let empty = empty.clone();
+        thread::spawn(move || empty.pop())
+    };
+    let pusher = {
+        let full = full.clone();
+        thread::spawn(move || full.push(1))
+    };
+
+    thread::sleep(Duration::from_millis(50));
+    empty.close();
+    full.close();
+    assert_eq!(popper.join().unwrap(), None);
+    assert_eq!(pusher.join().unwrap(), Err(Closed(1)));
+    assert_eq!(full.pop(), Some(0));
+}
+
+#[test]
+fn test_capacity_blocks_push() {
+    let q = Arc::new(SyncQueue::with_capacity(2));
+    q.push('a').unwrap();
+    q.push('b').unwrap();
+
+    let pusher = {
+        let q = q.clone();
+        thread::spawn(move || q.push('c'))
+    };
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(q.len(), 2);
+    assert_eq!(q.pop(), Some('a'));
+    pusher.join().unwrap().unwrap();
+    assert_eq!(q.pop(), Some('b'));
+    assert_eq!(q.pop(), Some('c'));
+}
+
+#[test]
+fn test_pop_timeout_max() {
+    let q = Arc::new(SyncQueue::new());
+    let pusher = {
+        let q = q.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            q.push(7).unwrap();
+            q.close();
+        })
+    };
+    assert_eq!(q.pop_timeout(Duration::MAX), Ok(7));
+    assert_eq!(q.pop_timeout(Duration::MAX), Err(PopTimeoutError::Closed));
+    pusher.join().unwrap();
+}
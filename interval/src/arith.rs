@@ -0,0 +1,327 @@
+//! Interval arithmetic on `Interval<f64>`.
+//!
+//! Each operation returns a closed interval guaranteed to contain every value
+//! the operation could produce from values in its operands: if `x` is in `a`
+//! and `y` is in `b`, then `x + y` is in `a + b`, even allowing for rounding.
+//! Since Rust gives us no control over the floating-point rounding mode, each
+//! computed bound is simply moved one unit in the last place outward. IEEE 754
+//! addition, subtraction, multiplication, division and square root are all
+//! correctly rounded, so the true result always lies within that distance.
+//!
+//! Operands are treated as their closures: `(1, 2)` is handled as `[1, 2]`,
+//! and unbounded ends as infinities. An empty operand gives an empty result.
+
+use crate::Interval;
+use std::ops::Bound::{Excluded, Included, Unbounded};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+impl Interval<f64> {
+    /// Return this interval's closure as a pair of endpoints, using infinities
+    /// for unbounded ends, or `None` if the interval is empty.
+    fn endpoints(&self) -> Option<(f64, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+        let lower = match self.lower {
+            Included(l) | Excluded(l) => l,
+            Unbounded => f64::NEG_INFINITY,
+        };
+        let upper = match self.upper {
+            Included(u) | Excluded(u) => u,
+            Unbounded => f64::INFINITY,
+        };
+        Some((lower, upper))
+    }
+
+    /// Return the closed interval `[lower, upper]`, exactly as given.
+    /// Infinite ends become unbounded.
+    fn from_endpoints(lower: f64, upper: f64) -> Interval<f64> {
+        Interval {
+            lower: if lower == f64::NEG_INFINITY { Unbounded } else { Included(lower) },
+            upper: if upper == f64::INFINITY { Unbounded } else { Included(upper) },
+        }
+    }
+
+    /// Return `[lower, upper]` widened by one unit in the last place on each
+    /// side, to cover rounding error in their computation.
+    fn outward(lower: f64, upper: f64) -> Interval<f64> {
+        Interval::from_endpoints(lower.next_down(), upper.next_up())
+    }
+
+    fn empty() -> Interval<f64> {
+        Interval { lower: Excluded(0.0), upper: Excluded(0.0) }
+    }
+
+    fn entire() -> Interval<f64> {
+        Interval { lower: Unbounded, upper: Unbounded }
+    }
+
+    /// Return the midpoint of the interval. Following IEEE 1788, the midpoint
+    /// of `(-∞, ∞)` is zero, and the midpoint of an interval unbounded on only
+    /// one side is the most extreme finite value in that direction. Return
+    /// NaN if the interval is empty.
+    ///
+    /// The result always lies within the interval, but it is not necessarily
+    /// the exact midpoint.
+    pub fn mid(&self) -> f64 {
+        match self.endpoints() {
+            None => f64::NAN,
+            Some((l, u)) if l == f64::NEG_INFINITY && u == f64::INFINITY => 0.0,
+            Some((l, _)) if l == f64::NEG_INFINITY => f64::MIN,
+            Some((_, u)) if u == f64::INFINITY => f64::MAX,
+            // Halving first avoids overflow.
+            Some((l, u)) => (0.5 * l + 0.5 * u).max(l).min(u),
+        }
+    }
+
+    /// Return an upper bound on the interval's width, `upper - lower`. This is
+    /// infinite if either end is unbounded, and zero if the interval is empty.
+    pub fn width(&self) -> f64 {
+        match self.endpoints() {
+            None => 0.0,
+            Some((l, u)) => {
+                let width = u - l;
+                if width == 0.0 { 0.0 } else { width.next_up() }
+            }
+        }
+    }
+
+    /// Return an enclosure of the square roots of the non-negative values in
+    /// this interval. If the interval has no non-negative values, return an
+    /// empty interval.
+    pub fn sqrt(&self) -> Interval<f64> {
+        match self.endpoints() {
+            Some((l, u)) if u >= 0.0 => {
+                let lower = if l <= 0.0 { 0.0 } else { l.sqrt().next_down().max(0.0) };
+                Interval::from_endpoints(lower, u.sqrt().next_up())
+            }
+            _ => Interval::empty(),
+        }
+    }
+
+    /// Return an enclosure of `x.powi(n)` for every `x` in this interval.
+    ///
+    /// Unlike computing `self * self * ...`, this knows that both factors are
+    /// the same value, so `[-1, 2].powi(2)` is `[0, 4]`, not `[-2, 4]`.
+    pub fn powi(&self, n: i32) -> Interval<f64> {
+        if n < 0 {
+            Interval::from_endpoints(1.0, 1.0) / self.powi_unsigned(n.unsigned_abs())
+        } else {
+            self.powi_unsigned(n as u32)
+        }
+    }
+
+    fn powi_unsigned(&self, n: u32) -> Interval<f64> {
+        let (l, u) = match self.endpoints() {
+            None => return Interval::empty(),
+            Some(ends) => ends,
+        };
+        if n == 0 {
+            return Interval::from_endpoints(1.0, 1.0);
+        }
+
+        // x^n is increasing for odd n. For even n it depends only on |x|, in
+        // which it is increasing, so find the range of |x| first.
+        let (l, u) = if n % 2 == 1 || l >= 0.0 {
+            (l, u)
+        } else if u <= 0.0 {
+            (-u, -l)
+        } else {
+            (0.0, u.max(-l))
+        };
+        let (lower, _) = power(l, n);
+        let (_, upper) = power(u, n);
+        Interval::from_endpoints(lower, upper)
+    }
+}
+
+/// Return bounds on `x^n`, computed by repeated squaring.
+fn power(x: f64, mut n: u32) -> (f64, f64) {
+    let mut result = Interval::from_endpoints(1.0, 1.0);
+    let mut square = Interval::from_endpoints(x, x);
+    loop {
+        if n & 1 == 1 {
+            result = result * square;
+        }
+        n >>= 1;
+        if n == 0 {
+            return result.endpoints().unwrap();
+        }
+        square = square * square;
+    }
+}
+
+/// Multiply `a` by `b`, treating zero times infinity as zero: the infinity
+/// stands in for an unbounded end, which no actual value reaches.
+fn times(a: f64, b: f64) -> f64 {
+    if a == 0.0 || b == 0.0 { 0.0 } else { a * b }
+}
+
+impl Neg for Interval<f64> {
+    type Output = Interval<f64>;
+    fn neg(self) -> Interval<f64> {
+        match self.endpoints() {
+            None => Interval::empty(),
+            Some((l, u)) => Interval::from_endpoints(-u, -l),
+        }
+    }
+}
+
+impl Add for Interval<f64> {
+    type Output = Interval<f64>;
+    fn add(self, rhs: Interval<f64>) -> Interval<f64> {
+        match (self.endpoints(), rhs.endpoints()) {
+            (Some((al, au)), Some((bl, bu))) => Interval::outward(al + bl, au + bu),
+            _ => Interval::empty(),
+        }
+    }
+}
+
+impl Sub for Interval<f64> {
+    type Output = Interval<f64>;
+    fn sub(self, rhs: Interval<f64>) -> Interval<f64> {
+        match (self.endpoints(), rhs.endpoints()) {
+            (Some((al, au)), Some((bl, bu))) => Interval::outward(al - bu, au - bl),
+            _ => Interval::empty(),
+        }
+    }
+}
+
+impl Mul for Interval<f64> {
+    type Output = Interval<f64>;
+    fn mul(self, rhs: Interval<f64>) -> Interval<f64> {
+        let ((al, au), (bl, bu)) = match (self.endpoints(), rhs.endpoints()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Interval::empty(),
+        };
+        let products = [times(al, bl), times(al, bu), times(au, bl), times(au, bu)];
+        let lower = products.iter().copied().fold(f64::INFINITY, f64::min);
+        let upper = products.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Interval::outward(lower, upper)
+    }
+}
+
+impl Div for Interval<f64> {
+    type Output = Interval<f64>;
+
+    /// Divide `self` by `rhs`. If `rhs` contains zero, the quotients are
+    /// unbounded, so return `(-∞, ∞)`.
+    fn div(self, rhs: Interval<f64>) -> Interval<f64> {
+        let ((al, au), (bl, bu)) = match (self.endpoints(), rhs.endpoints()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Interval::empty(),
+        };
+        if bl <= 0.0 && 0.0 <= bu {
+            return Interval::entire();
+        }
+        // Infinity divided by infinity is NaN, but `min` and `max` ignore
+        // NaNs, and the other quotients still bound the result.
+        let quotients = [al / bl, al / bu, au / bl, au / bu];
+        let lower = quotients.iter().copied().fold(f64::INFINITY, f64::min);
+        let upper = quotients.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Interval::outward(lower, upper)
+    }
+}
+
+#[cfg(test)]
+fn closed(lower: f64, upper: f64) -> Interval<f64> {
+    Interval::closed(lower, upper).unwrap()
+}
+
+/// Assert that `result` contains `[lower, upper]` and is at most a few units in
+/// the last place wider.
+#[cfg(test)]
+fn assert_encloses(result: Interval<f64>, lower: f64, upper: f64) {
+    let (l, u) = result.endpoints().expect("result is empty");
+    assert!(l <= lower && upper <= u, "{} doesn't enclose [{}, {}]", result, lower, upper);
+    let close = |bound: f64, exact: f64| {
+        exact.is_infinite() || (bound - exact).abs() <= 4.0 * f64::EPSILON * exact.abs().max(1.0)
+    };
+    assert!(close(l, lower) && close(u, upper), "{} too wide", result);
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_encloses(closed(1.0, 2.0) + closed(3.0, 4.0), 4.0, 6.0);
+    assert_encloses(closed(1.0, 2.0) - closed(3.0, 4.0), -3.0, -1.0);
+    assert_encloses(closed(-1.0, 2.0) * closed(3.0, 4.0), -4.0, 8.0);
+    assert_encloses(closed(-2.0, -1.0) * closed(-4.0, 3.0), -6.0, 8.0);
+    assert_encloses(closed(1.0, 2.0) / closed(4.0, 8.0), 0.125, 0.5);
+    assert_encloses(closed(-1.0, 2.0) / closed(-4.0, -2.0), -1.0, 0.5);
+    assert_encloses(-closed(1.0, 2.0), -2.0, -1.0);
+
+    // Dividing by an interval containing zero gives everything.
+    assert_eq!(closed(1.0, 2.0) / closed(-1.0, 1.0), Interval::from(..));
+
+    // Rounding error is covered: 0.1 + 0.2 isn't 0.3 in floating point, but
+    // the interval sum contains the exact sum of the two doubles.
+    let sum = closed(0.1, 0.1) + closed(0.2, 0.2);
+    assert!(sum.contains(&(0.1 + 0.2)));
+    assert!(sum.contains(&0.30000000000000004));
+    assert!(sum.width() > 0.0);
+
+    // Empty operands give empty results.
+    let empty = Interval::open(1.0, 1.0).unwrap();
+    assert!((empty + closed(1.0, 2.0)).is_empty());
+    assert!((closed(1.0, 2.0) * empty).is_empty());
+}
+
+#[test]
+fn test_unbounded() {
+    let positive = Interval::from(1.0..);
+    assert_encloses(positive + closed(1.0, 2.0), 2.0, f64::INFINITY);
+    assert_eq!((positive + closed(1.0, 2.0)).upper(), Unbounded);
+    assert_encloses(closed(0.0, 1.0) * positive, 0.0, f64::INFINITY);
+    assert_encloses(closed(0.0, 0.0) * Interval::from(..), 0.0, 0.0);
+    assert_encloses(closed(1.0, 2.0) / positive, 0.0, 2.0);
+    assert_encloses(positive / positive, 0.0, f64::INFINITY);
+
+    // Open ends are closed.
+    assert_encloses(Interval::open(1.0, 2.0).unwrap() + closed(0.0, 0.0), 1.0, 2.0);
+
+    // Overflow is still enclosed.
+    assert_encloses(closed(f64::MAX, f64::MAX) * closed(2.0, 2.0), f64::MAX, f64::INFINITY);
+}
+
+#[test]
+fn test_sqrt_powi() {
+    assert_encloses(closed(4.0, 9.0).sqrt(), 2.0, 3.0);
+    assert_encloses(closed(-4.0, 9.0).sqrt(), 0.0, 3.0);
+    assert_eq!(closed(-4.0, 9.0).sqrt().lower(), Included(&0.0));
+    assert!(closed(-9.0, -4.0).sqrt().is_empty());
+    let root2 = closed(2.0, 2.0).sqrt();
+    assert!(root2.contains(&std::f64::consts::SQRT_2));
+    assert!((root2 * root2).contains(&2.0));
+
+    assert_encloses(closed(-1.0, 2.0).powi(2), 0.0, 4.0);
+    assert_encloses(closed(-3.0, -2.0).powi(2), 4.0, 9.0);
+    assert_encloses(closed(-1.0, 2.0).powi(3), -1.0, 8.0);
+    assert_encloses(closed(-2.0, 3.0).powi(0), 1.0, 1.0);
+    assert_encloses(closed(2.0, 4.0).powi(-2), 0.0625, 0.25);
+    // Each squaring widens the enclosure a little.
+    let p = closed(1.5, 1.5).powi(10);
+    assert!(p.contains(&57.6650390625) && p.width() < 1e-12);
+    assert_eq!(closed(-1.0, 1.0).powi(-1), Interval::from(..));
+}
+
+#[test]
+fn test_mid_width() {
+    assert_eq!(closed(1.0, 3.0).mid(), 2.0);
+    assert_eq!(closed(f64::MAX, f64::MAX).mid(), f64::MAX);
+    assert_eq!(closed(-f64::MAX, f64::MAX).mid(), 0.0);
+    assert_eq!(Interval::<f64>::from(..).mid(), 0.0);
+    assert_eq!(Interval::from(0.0..).mid(), f64::MAX);
+    assert!(Interval::open(1.0, 1.0).unwrap().mid().is_nan());
+
+    assert!(closed(1.0, 3.0).width() >= 2.0);
+    assert_eq!(closed(1.0, 1.0).width(), 0.0);
+    assert_eq!(Interval::from(..0.0).width(), f64::INFINITY);
+
+    // Propagating a measurement: a 10 ± 0.1 m fall takes t = sqrt(2h / g).
+    let h = closed(9.9, 10.1);
+    let g = closed(9.80665, 9.80665);
+    let t = (closed(2.0, 2.0) * h / g).sqrt();
+    assert!(t.contains(&(2.0 * 9.9 / 9.80665_f64).sqrt()));
+    assert!(t.contains(&(2.0 * 10.1 / 9.80665_f64).sqrt()));
+    assert!((t.mid() - 1.428).abs() < 1e-3);
+}
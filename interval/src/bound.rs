@@ -0,0 +1,133 @@
+//! Comparisons between interval bounds.
+//!
+//! A lower bound and an upper bound with the same value mean different things,
+//! as do included and excluded bounds, so `Bound` can't simply be ordered by
+//! value. These functions compare bounds according to the values they admit,
+//! treating `T` as continuous: nothing here knows that there are no integers
+//! strictly between 1 and 2.
+
+use std::cmp::Ordering::{self, Equal, Greater, Less};
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+
+/// Compare two lower bounds: the one admitting more values is less.
+pub(crate) fn cmp_lower<T: PartialOrd>(a: &Bound<T>, b: &Bound<T>) -> Option<Ordering> {
+    match (a, b) {
+        (Unbounded, Unbounded) => Some(Equal),
+        (Unbounded, _) => Some(Less),
+        (_, Unbounded) => Some(Greater),
+        (Included(x), Included(y)) | (Excluded(x), Excluded(y)) => x.partial_cmp(y),
+        (Included(x), Excluded(y)) => x.partial_cmp(y).map(|o| o.then(Less)),
+        (Excluded(x), Included(y)) => x.partial_cmp(y).map(|o| o.then(Greater)),
+    }
+}
+
+/// Compare two upper bounds: the one admitting more values is greater.
+pub(crate) fn cmp_upper<T: PartialOrd>(a: &Bound<T>, b: &Bound<T>) -> Option<Ordering> {
+    match (a, b) {
+        (Unbounded, Unbounded) => Some(Equal),
+        (Unbounded, _) => Some(Greater),
+        (_, Unbounded) => Some(Less),
+        (Included(x), Included(y)) | (Excluded(x), Excluded(y)) => x.partial_cmp(y),
+        (Included(x), Excluded(y)) => x.partial_cmp(y).map(|o| o.then(Greater)),
+        (Excluded(x), Included(y)) => x.partial_cmp(y).map(|o| o.then(Less)),
+    }
+}
+
+/// Return true if `value` is at or above the lower bound `lower`.
+pub(crate) fn above_lower<T: PartialOrd>(lower: &Bound<T>, value: &T) -> bool {
+    match lower {
+        Unbounded => true,
+        Included(x) => x <= value,
+        Excluded(x) => x < value,
+    }
+}
+
+/// Return true if `value` is at or below the upper bound `upper`.
+pub(crate) fn below_upper<T: PartialOrd>(upper: &Bound<T>, value: &T) -> bool {
+    match upper {
+        Unbounded => true,
+        Included(x) => value <= x,
+        Excluded(x) => value < x,
+    }
+}
+
+/// Return true if no value is both below `upper` and above `lower`: that is,
+/// an interval ending at `upper` lies entirely before one starting at `lower`.
+pub(crate) fn separated<T: PartialOrd>(upper: &Bound<T>, lower: &Bound<T>) -> bool {
+    match (upper, lower) {
+        (Unbounded, _) | (_, Unbounded) => false,
+        (Included(x), Included(y)) => x < y,
+        (Included(x), Excluded(y)) | (Excluded(x), Included(y)) | (Excluded(x), Excluded(y)) => {
+            x <= y
+        }
+    }
+}
+
+/// Return true if some value lies between an interval ending at `upper` and
+/// one starting at `lower`, so that their union would have a gap in it.
+/// Intervals that overlap, or that touch, like `[1, 2)` and `[2, 3]`, have no
+/// gap between them.
+pub(crate) fn gap<T: PartialOrd>(upper: &Bound<T>, lower: &Bound<T>) -> bool {
+    match (upper, lower) {
+        (Unbounded, _) | (_, Unbounded) => false,
+        (Excluded(x), Excluded(y)) => x <= y,
+        (Included(x), Included(y)) | (Included(x), Excluded(y)) | (Excluded(x), Included(y)) => {
+            x < y
+        }
+    }
+}
+
+/// Return the bound admitting exactly the values `bound` doesn't, turning a
+/// lower bound into an upper bound or vice versa. An unbounded end admits
+/// everything, so it has no complement.
+pub(crate) fn complement<T: Clone>(bound: &Bound<T>) -> Option<Bound<T>> {
+    match bound {
+        Included(x) => Some(Excluded(x.clone())),
+        Excluded(x) => Some(Included(x.clone())),
+        Unbounded => None,
+    }
+}
+
+/// Return the lesser of two lower bounds, preferring `a` when they're
+/// incomparable.
+pub(crate) fn min_lower<'a, T: PartialOrd>(a: &'a Bound<T>, b: &'a Bound<T>) -> &'a Bound<T> {
+    if cmp_lower(b, a) == Some(Less) { b } else { a }
+}
+
+pub(crate) fn max_lower<'a, T: PartialOrd>(a: &'a Bound<T>, b: &'a Bound<T>) -> &'a Bound<T> {
+    if cmp_lower(b, a) == Some(Greater) { b } else { a }
+}
+
+pub(crate) fn min_upper<'a, T: PartialOrd>(a: &'a Bound<T>, b: &'a Bound<T>) -> &'a Bound<T> {
+    if cmp_upper(b, a) == Some(Less) { b } else { a }
+}
+
+pub(crate) fn max_upper<'a, T: PartialOrd>(a: &'a Bound<T>, b: &'a Bound<T>) -> &'a Bound<T> {
+    if cmp_upper(b, a) == Some(Greater) { b } else { a }
+}
+
+#[test]
+fn test_ordering() {
+    assert_eq!(cmp_lower(&Included(1), &Excluded(1)), Some(Less));
+    assert_eq!(cmp_lower(&Unbounded, &Included(-100)), Some(Less));
+    assert_eq!(cmp_lower(&Excluded(1), &Included(2)), Some(Less));
+    assert_eq!(cmp_upper(&Included(1), &Excluded(1)), Some(Greater));
+    assert_eq!(cmp_upper(&Unbounded, &Included(100)), Some(Greater));
+    assert_eq!(cmp_upper(&Included(f64::NAN), &Included(1.0)), None);
+}
+
+#[test]
+fn test_separated_and_gap() {
+    // ..1] and [1..: both admit 1.
+    assert!(!separated(&Included(1), &Included(1)));
+    assert!(!gap(&Included(1), &Included(1)));
+    // ..1) and [1..: touching.
+    assert!(separated(&Excluded(1), &Included(1)));
+    assert!(!gap(&Excluded(1), &Included(1)));
+    // ..1) and (1..: 1 itself is missing.
+    assert!(separated(&Excluded(1), &Excluded(1)));
+    assert!(gap(&Excluded(1), &Excluded(1)));
+    // Unbounded ends reach everything.
+    assert!(!separated(&Unbounded, &Included(5)));
+    assert!(!gap(&Included(0), &Unbounded));
+}
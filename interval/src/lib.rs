@@ -1,10 +1,219 @@
 #![warn(rust_2018_idioms)]
 #![allow(elided_lifetimes_in_paths)]
 
-#[derive(Debug, PartialEq)]
-struct Interval<T> {
-    lower: T, // inclusive
-    upper: T, // exclusive
+use std::fmt;
+use std::ops::Bound::{self, Excluded, Included, Unbounded};
+use std::ops::{Range, RangeBounds, RangeFrom, RangeFull, RangeInclusive, RangeTo,
+               RangeToInclusive};
+
+mod arith;
+mod bound;
+mod map;
+mod relation;
+pub mod schedule;
+mod set;
+mod tree;
+
+pub use map::{IntervalMap, MapKey};
+pub use relation::AllenRelation;
+pub use set::IntervalSet;
+pub use tree::{IntervalTree, IntervalTreeIter};
+
+use bound::{max_lower, max_upper, min_lower, min_upper, separated};
+
+/// A range of values, each end of which may be included, excluded, or
+/// unbounded.
+///
+/// `new` makes half-open intervals like Rust's `a..b`, but `from_bounds` and
+/// the `From` conversions from Rust's range types can make any combination,
+/// like `(a, b]` or `[a, ∞)`.
+///
+/// An interval may be empty, like `[3, 3)`. The constructors refuse inverted
+/// intervals like `[5, 1]`, but the `From` conversions accept them, just as
+/// `5..1` is simply an empty range. Comparisons treat `T` as continuous: `(1,
+/// 2)` is not considered empty, even for integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    lower: Bound<T>,
+    upper: Bound<T>,
+}
+
+impl<T: PartialOrd> Interval<T> {
+    /// Return the interval from `lower` up to but not including `upper`, or
+    /// `None` if `lower` is greater than `upper`, or they can't be compared.
+    pub fn new(lower: T, upper: T) -> Option<Interval<T>> {
+        Interval::from_bounds(Included(lower), Excluded(upper))
+    }
+
+    /// Return the closed interval `[lower, upper]`, or `None` as for `new`.
+    pub fn closed(lower: T, upper: T) -> Option<Interval<T>> {
+        Interval::from_bounds(Included(lower), Included(upper))
+    }
+
+    /// Return the open interval `(lower, upper)`, or `None` as for `new`.
+    pub fn open(lower: T, upper: T) -> Option<Interval<T>> {
+        Interval::from_bounds(Excluded(lower), Excluded(upper))
+    }
+
+    /// Return the interval with the given bounds, or `None` if the lower
+    /// bound's value is greater than the upper bound's, or they can't be
+    /// compared.
+    pub fn from_bounds(lower: Bound<T>, upper: Bound<T>) -> Option<Interval<T>> {
+        let valid = match (bound_value(&lower), bound_value(&upper)) {
+            (Some(l), Some(u)) => l <= u,
+            _ => true,
+        };
+        if valid {
+            Some(Interval { lower, upper })
+        } else {
+            None
+        }
+    }
+
+    /// Return the interval's lower bound.
+    pub fn lower(&self) -> Bound<&T> {
+        self.lower.as_ref()
+    }
+
+    /// Return the interval's upper bound.
+    pub fn upper(&self) -> Bound<&T> {
+        self.upper.as_ref()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        separated(&self.upper, &self.lower)
+    }
+
+    /// Return true if `value` falls within this interval.
+    pub fn contains(&self, value: &T) -> bool {
+        bound::above_lower(&self.lower, value) && bound::below_upper(&self.upper, value)
+    }
+
+    /// Return true if some value falls within both `self` and `other`.
+    /// Intervals that merely touch, like `[1, 2)` and `[2, 3)`, don't overlap.
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        !separated(&self.upper, &other.lower) && !separated(&other.upper, &self.lower)
+            && !self.is_empty() && !other.is_empty()
+    }
+}
+
+impl<T: PartialOrd + Clone> Interval<T> {
+    /// Return the values that fall within both `self` and `other`, or `None`
+    /// if there are none.
+    pub fn intersection(&self, other: &Interval<T>) -> Option<Interval<T>> {
+        if !self.overlaps(other) {
+            return None;
+        }
+        let lower = max_lower(&self.lower, &other.lower);
+        let upper = min_upper(&self.upper, &other.upper);
+        Some(Interval { lower: lower.clone(), upper: upper.clone() })
+    }
+
+    /// Return the smallest interval containing both `self` and `other`,
+    /// including any gap between them. Empty intervals contribute nothing.
+    pub fn hull(&self, other: &Interval<T>) -> Interval<T> {
+        if other.is_empty() {
+            return self.clone();
+        } else if self.is_empty() {
+            return other.clone();
+        }
+        let lower = min_lower(&self.lower, &other.lower);
+        let upper = max_upper(&self.upper, &other.upper);
+        Interval { lower: lower.clone(), upper: upper.clone() }
+    }
+}
+
+impl<T> Interval<T>
+where
+    T: PartialOrd + Clone + std::ops::Sub<Output = T>,
+{
+    /// Return the length of the interval, `upper - lower`, whether or not the
+    /// ends are included, or `None` if either end is unbounded. An inverted
+    /// interval like `[5, 1)` has a length of zero.
+    pub fn len(&self) -> Option<T> {
+        match (bound_value(&self.lower), bound_value(&self.upper)) {
+            // `l - l` is zero, without asking `T` for a zero of its own.
+            (Some(l), Some(u)) if u > l => Some(u.clone() - l.clone()),
+            (Some(l), Some(_)) => Some(l.clone() - l.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// Return the value at which `bound` lies, if it's not unbounded.
+fn bound_value<T>(bound: &Bound<T>) -> Option<&T> {
+    match bound {
+        Included(v) | Excluded(v) => Some(v),
+        Unbounded => None,
+    }
+}
+
+impl<T> RangeBounds<T> for Interval<T> {
+    fn start_bound(&self) -> Bound<&T> {
+        self.lower.as_ref()
+    }
+
+    fn end_bound(&self) -> Bound<&T> {
+        self.upper.as_ref()
+    }
+}
+
+impl<T> From<Range<T>> for Interval<T> {
+    fn from(range: Range<T>) -> Self {
+        Interval { lower: Included(range.start), upper: Excluded(range.end) }
+    }
+}
+
+impl<T> From<RangeInclusive<T>> for Interval<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        let (start, end) = range.into_inner();
+        Interval { lower: Included(start), upper: Included(end) }
+    }
+}
+
+impl<T> From<RangeFrom<T>> for Interval<T> {
+    fn from(range: RangeFrom<T>) -> Self {
+        Interval { lower: Included(range.start), upper: Unbounded }
+    }
+}
+
+impl<T> From<RangeTo<T>> for Interval<T> {
+    fn from(range: RangeTo<T>) -> Self {
+        Interval { lower: Unbounded, upper: Excluded(range.end) }
+    }
+}
+
+impl<T> From<RangeToInclusive<T>> for Interval<T> {
+    fn from(range: RangeToInclusive<T>) -> Self {
+        Interval { lower: Unbounded, upper: Included(range.end) }
+    }
+}
+
+impl<T> From<RangeFull> for Interval<T> {
+    fn from(_: RangeFull) -> Self {
+        Interval { lower: Unbounded, upper: Unbounded }
+    }
+}
+
+impl<T> From<(Bound<T>, Bound<T>)> for Interval<T> {
+    fn from((lower, upper): (Bound<T>, Bound<T>)) -> Self {
+        Interval { lower, upper }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Interval<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.lower {
+            Included(l) => write!(f, "[{}, ", l)?,
+            Excluded(l) => write!(f, "({}, ", l)?,
+            Unbounded => write!(f, "(-∞, ")?,
+        }
+        match &self.upper {
+            Included(u) => write!(f, "{}]", u),
+            Excluded(u) => write!(f, "{})", u),
+            Unbounded => write!(f, "∞)"),
+        }
+    }
 }
 
 use std::cmp::{Ordering, PartialOrd};
@@ -13,9 +222,9 @@ impl<T: PartialOrd> PartialOrd<Interval<T>> for Interval<T> {
     fn partial_cmp(&self, other: &Interval<T>) -> Option<Ordering> {
         if self == other {
             Some(Ordering::Equal)
-        } else if self.lower >= other.upper {
+        } else if separated(&other.upper, &self.lower) {
             Some(Ordering::Greater)
-        } else if self.upper <= other.lower {
+        } else if separated(&self.upper, &other.lower) {
             Some(Ordering::Less)
         } else {
             None
@@ -24,15 +233,137 @@ impl<T: PartialOrd> PartialOrd<Interval<T>> for Interval<T> {
 }
 
 #[test]
+// Negates partial comparisons on purpose, to show how they differ from the
+// opposite comparison.
+#[allow(clippy::neg_cmp_op_on_partial_ord)]
 fn test() {
-    assert!(Interval { lower: 10, upper: 20 } <  Interval { lower: 20, upper: 40 });
-    assert!(Interval { lower: 7,  upper: 8  } >= Interval { lower: 0,  upper: 1  });
-    assert!(Interval { lower: 7,  upper: 8  } <= Interval { lower: 7,  upper: 8  });
-    assert!(Interval { lower: 7,  upper: 8  }.le(&Interval { lower: 7,  upper: 8  }));
+    assert!(Interval::from(10..20) <  Interval::from(20..40));
+    assert!(Interval::from(7..8)   >= Interval::from(0..1));
+    assert!(Interval::from(7..8)   <= Interval::from(7..8));
+    assert!(Interval::from(7..8).le(&Interval::from(7..8)));
 
     // Overlapping intervals aren't ordered with respect to each other.
-    let left  = Interval { lower: 10, upper: 30 };
-    let right = Interval { lower: 20, upper: 40 };
+    let left  = Interval::from(10..30);
+    let right = Interval::from(20..40);
     assert!(!(left < right));
     assert!(!(left >= right));
+
+    // Closed intervals sharing an endpoint overlap.
+    assert!(Interval::from(10..=20).partial_cmp(&Interval::from(20..=30)).is_none());
+    assert!(Interval::from(..20) < Interval::from(20..));
+}
+
+
+#[test]
+fn test_len() {
+    assert_eq!(Interval::from(1u64..5).len(), Some(4));
+    assert_eq!(Interval::from(3u64..3).len(), Some(0));
+    assert_eq!(Interval::from((Included(5u64), Excluded(1))).len(), Some(0));
+    assert_eq!(Interval::from((Included(5), Included(1))).len(), Some(0));
+    assert_eq!(Interval::from(4.0..2.5).len(), Some(0.0));
+    assert_eq!(Interval::from(..5u64).len(), None);
+}
+#[test]
+fn test_new() {
+    assert_eq!(Interval::new(1, 5), Some(Interval::from(1..5)));
+    assert_eq!(Interval::new(3, 3), Some(Interval::from(3..3)));
+    assert_eq!(Interval::new(5, 1), None);
+    assert_eq!(Interval::new(0.0, f64::NAN), None);
+    assert_eq!(Interval::closed(5, 1), None);
+    assert_eq!(Interval::closed(1, 5), Some(Interval::from(1..=5)));
+    assert_eq!(Interval::from_bounds(Unbounded, Excluded(1)), Some(Interval::from(..1)));
+
+    let i = Interval::new(2.5, 4.0).unwrap();
+    assert_eq!((i.lower(), i.upper()), (Included(&2.5), Excluded(&4.0)));
+    assert_eq!(i.len(), Some(1.5));
+    assert_eq!(Interval::from(3..).len(), None);
+    assert!(!i.is_empty());
+    assert!(Interval::new(3, 3).unwrap().is_empty());
+    assert!(Interval::open(3, 3).unwrap().is_empty());
+    assert!(!Interval::closed(3, 3).unwrap().is_empty());
+    assert!(!Interval::<i32>::from(..).is_empty());
+
+    #[allow(clippy::reversed_empty_ranges)]
+    let inverted = Interval::from(5..=1);
+    assert!(inverted.is_empty());
+}
+
+#[test]
+fn test_contains_overlaps() {
+    let i = Interval::new(10, 20).unwrap();
+    assert!(i.contains(&10));
+    assert!(i.contains(&19));
+    assert!(!i.contains(&20));
+    assert!(!i.contains(&9));
+
+    assert!(i.overlaps(&Interval::new(15, 25).unwrap()));
+    assert!(i.overlaps(&Interval::new(12, 13).unwrap()));
+    assert!(!i.overlaps(&Interval::new(20, 30).unwrap()));
+    assert!(!i.overlaps(&Interval::new(0, 10).unwrap()));
+    assert!(!i.overlaps(&Interval::new(15, 15).unwrap()));
+
+    let open = Interval::open(10, 20).unwrap();
+    assert!(!open.contains(&10));
+    assert!(open.contains(&11));
+    assert!(!open.contains(&20));
+    assert!(Interval::from(..=20).contains(&20));
+    assert!(Interval::from(..=20).contains(&i32::MIN));
+    assert!(Interval::from(20..).contains(&i32::MAX));
+    assert!(Interval::<i32>::from(..).contains(&0));
+
+    assert!(Interval::from(0..=10).overlaps(&Interval::from(10..20)));
+    assert!(!Interval::open(0, 10).unwrap().overlaps(&Interval::from(10..20)));
+    assert!(Interval::from(..5).overlaps(&Interval::from(4..)));
+    assert!(!Interval::from(..5).overlaps(&Interval::from(5..)));
+}
+
+#[test]
+fn test_intersection_hull() {
+    let a = Interval::new(10, 30).unwrap();
+    let b = Interval::new(20, 40).unwrap();
+    let c = Interval::new(50, 60).unwrap();
+
+    assert_eq!(a.intersection(&b), Interval::new(20, 30));
+    assert_eq!(b.intersection(&a), Interval::new(20, 30));
+    assert_eq!(a.intersection(&c), None);
+    assert_eq!(a.intersection(&Interval::new(30, 40).unwrap()), None);
+
+    assert_eq!(a.hull(&c), Interval::new(10, 60).unwrap());
+    assert_eq!(c.hull(&b), Interval::new(20, 60).unwrap());
+    assert_eq!(a.hull(&Interval::new(100, 100).unwrap()), a);
+
+    // Each side of the intersection takes the tighter bound, and each side of
+    // the hull the looser.
+    let x = Interval::from((Excluded(0), Included(10)));
+    let y = Interval::from(0..10);
+    assert_eq!(x.intersection(&y), Interval::open(0, 10));
+    assert_eq!(x.hull(&y), Interval::closed(0, 10).unwrap());
+    assert_eq!(x.intersection(&Interval::from(10..)), Interval::closed(10, 10));
+    assert_eq!(Interval::from(..5).hull(&Interval::from(7..)), Interval::from(..));
+}
+
+#[test]
+fn test_range_bounds() {
+    let i = Interval::from((Excluded(1_usize), Included(4)));
+    assert_eq!(i.start_bound(), Excluded(&1));
+    assert_eq!(i.end_bound(), Included(&4));
+
+    // Anything that accepts a range accepts an interval.
+    let mut v: Vec<i32> = (0..10).collect();
+    let drained: Vec<i32> = v.drain(i).collect();
+    assert_eq!(drained, vec![2, 3, 4]);
+
+    let squares: std::collections::BTreeMap<i32, i32> = (0..10).map(|k| (k, k * k)).collect();
+    let found: Vec<i32> = squares.range(Interval::from(2..=3)).map(|(_, v)| *v).collect();
+    assert_eq!(found, vec![4, 9]);
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(Interval::new(1, 5).unwrap().to_string(), "[1, 5)");
+    assert_eq!(Interval::new(-0.5, 2.25).unwrap().to_string(), "[-0.5, 2.25)");
+    assert_eq!(Interval::from((Excluded(1), Included(5))).to_string(), "(1, 5]");
+    assert_eq!(Interval::from(3..).to_string(), "[3, ∞)");
+    assert_eq!(Interval::from(..=3).to_string(), "(-∞, 3]");
+    assert_eq!(Interval::<i32>::from(..).to_string(), "(-∞, ∞)");
 }
@@ -0,0 +1,408 @@
+use crate::bound::{below_upper, complement, gap, separated};
+use crate::Interval;
+use std::fmt;
+use std::iter::FromIterator;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::Bound::{Excluded, Included};
+use std::time::SystemTime;
+
+/// A map from ranges of keys to values, represented as a sorted list of
+/// disjoint intervals, each with its value.
+///
+/// Inserting a range overwrites whatever the map held for those keys before,
+/// trimming or splitting the existing entries it overlaps. Like `IntervalSet`,
+/// the list is kept normalized: no interval is empty, and adjacent intervals
+/// with equal values are merged, so two maps assigning the same values to the
+/// same keys always compare equal.
+///
+/// For discrete keys like integers, the map stores every bounded end in the
+/// half-open form `[a, b)`, so that `[0, 99]` and `[100, 199]` count as
+/// touching, and are merged if their values are equal. See `MapKey`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalMap<K, V> {
+    entries: Vec<(Interval<K>, V)>,
+}
+
+/// A type that can serve as the key of an `IntervalMap`, or the element of an
+/// `IntervalSet`.
+///
+/// Interval comparisons treat keys as continuous, so `[0, 99]` and `[100,
+/// 199]` seem to leave a gap between 99 and 100. For discrete types,
+/// `successor` lets the map or set rewrite `x]` as `x + 1)` and `(x` as `[x +
+/// 1`, closing such gaps.
+pub trait MapKey: PartialOrd + Clone {
+    /// Return the least value greater than `self`, or `None` if there is no
+    /// such value, or the type is continuous, as the default assumes.
+    fn successor(&self) -> Option<Self> {
+        None
+    }
+}
+
+macro_rules! discrete_keys {
+    ($($t:ty)*) => {
+        $(impl MapKey for $t {
+            fn successor(&self) -> Option<Self> {
+                self.checked_add(1)
+            }
+        })*
+    }
+}
+
+discrete_keys!(u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize);
+
+impl MapKey for f32 {}
+impl MapKey for f64 {}
+impl MapKey for SystemTime {}
+
+impl MapKey for char {
+    fn successor(&self) -> Option<Self> {
+        match *self {
+            '\u{d7ff}' => Some('\u{e000}'),
+            c => std::char::from_u32(c as u32 + 1),
+        }
+    }
+}
+
+impl MapKey for Ipv4Addr {
+    fn successor(&self) -> Option<Self> {
+        u32::from(*self).checked_add(1).map(Ipv4Addr::from)
+    }
+}
+
+impl MapKey for Ipv6Addr {
+    fn successor(&self) -> Option<Self> {
+        u128::from(*self).checked_add(1).map(Ipv6Addr::from)
+    }
+}
+
+/// Rewrite `interval`'s bounds in half-open form, where its key type allows.
+pub(crate) fn normalize<K: MapKey>(interval: &Interval<K>) -> Interval<K> {
+    let mut interval = interval.clone();
+    if let Excluded(x) = &interval.lower {
+        if let Some(next) = x.successor() {
+            interval.lower = Included(next);
+        }
+    }
+    if let Included(x) = &interval.upper {
+        if let Some(next) = x.successor() {
+            interval.upper = Excluded(next);
+        }
+    }
+    interval
+}
+
+/// The entries from `start` to `end` overlap some interval, and `before` and
+/// `after` are the parts of the first and last of them, if any, that stick out
+/// past its ends.
+struct Cut<K, V> {
+    start: usize,
+    end: usize,
+    before: Option<(Interval<K>, V)>,
+    after: Option<(Interval<K>, V)>,
+}
+
+impl<K, V> IntervalMap<K, V> {
+    pub fn new() -> IntervalMap<K, V> {
+        IntervalMap { entries: Vec::new() }
+    }
+
+    /// Return the number of intervals in the map, after merging.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return the map's entries, in increasing order.
+    pub fn entries(&self) -> &[(Interval<K>, V)] {
+        &self.entries
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (Interval<K>, V)> {
+        self.entries.iter()
+    }
+}
+
+impl<K, V> Default for IntervalMap<K, V> {
+    fn default() -> Self {
+        IntervalMap::new()
+    }
+}
+
+impl<K: MapKey, V: Clone + PartialEq> IntervalMap<K, V> {
+    /// Map every key in `interval` to `value`, replacing any values they had.
+    pub fn insert(&mut self, interval: Interval<K>, value: V) {
+        let interval = normalize(&interval);
+        if interval.is_empty() {
+            return;
+        }
+        let cut = self.cut(&interval);
+        let replacement: Vec<_> = cut.before.into_iter()
+            .chain(Some((interval, value)))
+            .chain(cut.after)
+            .collect();
+        let count = replacement.len();
+        self.entries.splice(cut.start..cut.end, replacement);
+
+        // Only the new entry and its neighbours can have become mergeable.
+        self.coalesce(cut.start.saturating_sub(1), cut.start + count + 1);
+    }
+
+    /// Remove every key in `interval` from the map.
+    pub fn remove(&mut self, interval: &Interval<K>) {
+        let interval = &normalize(interval);
+        if interval.is_empty() {
+            return;
+        }
+        let cut = self.cut(interval);
+        self.entries.splice(cut.start..cut.end, cut.before.into_iter().chain(cut.after));
+    }
+
+    /// Find the entries overlapping `interval`, and whatever parts of them
+    /// lie outside it.
+    fn cut(&self, interval: &Interval<K>) -> Cut<K, V> {
+        let start = self.entries.partition_point(|(i, _)| separated(&i.upper, &interval.lower));
+        let end = self.entries.partition_point(|(i, _)| !separated(&interval.upper, &i.lower));
+        if start >= end {
+            return Cut { start, end: start, before: None, after: None };
+        }
+
+        let (first, first_value) = &self.entries[start];
+        let before = complement(&interval.lower)
+            .map(|upper| Interval { lower: first.lower.clone(), upper })
+            .filter(|before| !before.is_empty())
+            .map(|before| (before, first_value.clone()));
+        let (last, last_value) = &self.entries[end - 1];
+        let after = complement(&interval.upper)
+            .map(|lower| Interval { lower, upper: last.upper.clone() })
+            .filter(|after| !after.is_empty())
+            .map(|after| (after, last_value.clone()));
+        Cut { start, end, before, after }
+    }
+
+    /// Merge any touching entries with equal values among those from `from`
+    /// up to `to`.
+    fn coalesce(&mut self, from: usize, to: usize) {
+        let mut to = to.min(self.entries.len());
+        let mut i = from + 1;
+        while i < to {
+            let (prev, next) = (&self.entries[i - 1], &self.entries[i]);
+            if prev.1 == next.1 && !gap(&prev.0.upper, &next.0.lower) {
+                let (next, _) = self.entries.remove(i);
+                self.entries[i - 1].0.upper = next.upper;
+                to -= 1;
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    /// Return the value mapped to `key`, if any.
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.get_entry(key).map(|(_, value)| value)
+    }
+
+    /// Return the interval containing `key` and its value, if any.
+    pub fn get_entry(&self, key: &K) -> Option<(&Interval<K>, &V)> {
+        let index = self.entries.partition_point(|(i, _)| !below_upper(&i.upper, key));
+        match self.entries.get(index) {
+            Some((interval, value)) if interval.contains(key) => Some((interval, value)),
+            _ => None,
+        }
+    }
+
+    /// Return the entries whose intervals overlap `interval`, in order.
+    pub fn overlapping(&self, interval: &Interval<K>) -> &[(Interval<K>, V)] {
+        let interval = &normalize(interval);
+        if interval.is_empty() {
+            return &[];
+        }
+        let start = self.entries.partition_point(|(i, _)| separated(&i.upper, &interval.lower));
+        let end = self.entries.partition_point(|(i, _)| !separated(&interval.upper, &i.lower));
+        &self.entries[start..end.max(start)]
+    }
+}
+
+impl<K: MapKey, V: Clone + PartialEq> FromIterator<(Interval<K>, V)>
+    for IntervalMap<K, V>
+{
+    fn from_iter<I: IntoIterator<Item = (Interval<K>, V)>>(iter: I) -> Self {
+        let mut map = IntervalMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: MapKey, V: Clone + PartialEq> Extend<(Interval<K>, V)>
+    for IntervalMap<K, V>
+{
+    /// Insert each entry in turn, so later entries overwrite earlier ones.
+    fn extend<I: IntoIterator<Item = (Interval<K>, V)>>(&mut self, iter: I) {
+        for (interval, value) in iter {
+            self.insert(interval, value);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a IntervalMap<K, V> {
+    type Item = &'a (Interval<K>, V);
+    type IntoIter = std::slice::Iter<'a, (Interval<K>, V)>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl<K: fmt::Display, V: fmt::Display> fmt::Display for IntervalMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (interval, value)) in self.entries.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", interval, value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+fn iv(lower: u64, upper: u64) -> Interval<u64> {
+    Interval::new(lower, upper).unwrap()
+}
+
+#[test]
+fn test_insert() {
+    let mut m = IntervalMap::new();
+    m.insert(iv(0, 100), 'a');
+    m.insert(iv(40, 60), 'b');
+    assert_eq!(m.to_string(), "{[0, 40): a, [40, 60): b, [60, 100): a}");
+
+    // Overwriting trims the entries on either side and swallows those between.
+    m.insert(iv(30, 70), 'c');
+    assert_eq!(m.to_string(), "{[0, 30): a, [30, 70): c, [70, 100): a}");
+    m.insert(iv(50, 200), 'd');
+    assert_eq!(m.to_string(), "{[0, 30): a, [30, 50): c, [50, 200): d}");
+
+    // Equal values merge with their neighbours, touching or overlapping.
+    m.insert(iv(50, 60), 'c');
+    assert_eq!(m.to_string(), "{[0, 30): a, [30, 60): c, [60, 200): d}");
+    m.insert(iv(20, 40), 'c');
+    m.insert(iv(60, 300), 'c');
+    assert_eq!(m.to_string(), "{[0, 20): a, [20, 300): c}");
+    m.insert(iv(0, 20), 'c');
+    assert_eq!(m.entries(), &[(iv(0, 300), 'c')]);
+
+    // Empty intervals change nothing; gaps keep equal values apart.
+    m.insert(iv(5, 5), 'x');
+    m.insert(iv(400, 500), 'c');
+    assert_eq!(m.to_string(), "{[0, 300): c, [400, 500): c}");
+    assert_eq!(m.len(), 2);
+}
+
+#[test]
+fn test_remove() {
+    let mut m: IntervalMap<u64, &str> =
+        vec![(iv(0, 10), "x"), (iv(10, 20), "y"), (iv(30, 40), "z")].into_iter().collect();
+    m.remove(&iv(5, 35));
+    assert_eq!(m.to_string(), "{[0, 5): x, [35, 40): z}");
+    m.remove(&iv(36, 38));
+    assert_eq!(m.to_string(), "{[0, 5): x, [35, 36): z, [38, 40): z}");
+
+    // Filling the hole with the same value merges the pieces again.
+    m.insert(iv(36, 38), "z");
+    assert_eq!(m.to_string(), "{[0, 5): x, [35, 40): z}");
+    m.remove(&Interval::from(..));
+    assert!(m.is_empty());
+}
+
+#[test]
+fn test_ip_ranges() {
+    use std::net::Ipv4Addr;
+
+    fn net(addr: [u8; 4], prefix: u32) -> Interval<u32> {
+        let start = u32::from(Ipv4Addr::from(addr));
+        Interval::from(start..=start + (u32::MAX >> prefix))
+    }
+
+    let mut owners = IntervalMap::new();
+    owners.insert(net([10, 0, 0, 0], 8), "corp");
+    owners.insert(net([10, 1, 0, 0], 16), "lab");
+    owners.insert(net([10, 1, 2, 0], 24), "corp");
+    owners.insert(net([192, 168, 0, 0], 16), "home");
+
+    let owner = |a: [u8; 4]| owners.get(&u32::from(Ipv4Addr::from(a))).copied();
+    assert_eq!(owner([10, 0, 0, 1]), Some("corp"));
+    assert_eq!(owner([10, 1, 0, 1]), Some("lab"));
+    assert_eq!(owner([10, 1, 2, 3]), Some("corp"));
+    assert_eq!(owner([10, 1, 3, 0]), Some("lab"));
+    assert_eq!(owner([10, 255, 255, 255]), Some("corp"));
+    assert_eq!(owner([11, 0, 0, 0]), None);
+    assert_eq!(owner([192, 168, 1, 1]), Some("home"));
+    assert_eq!(owners.len(), 6);
+
+    // Handing the lab's range back to corp leaves a single corp entry, in
+    // half-open form.
+    owners.insert(net([10, 1, 0, 0], 16), "corp");
+    let corp = Interval::new(u32::from(Ipv4Addr::new(10, 0, 0, 0)),
+                             u32::from(Ipv4Addr::new(11, 0, 0, 0))).unwrap();
+    assert_eq!(owners.overlapping(&net([10, 0, 0, 0], 8)), &[(corp, "corp")]);
+}
+
+#[test]
+fn test_discrete_keys() {
+    // Closed integer ranges that leave no key out between them merge.
+    let mut m = IntervalMap::new();
+    m.insert(Interval::closed(0, 99).unwrap(), "a");
+    m.insert(Interval::closed(100, 199).unwrap(), "a");
+    assert_eq!(m.len(), 1);
+    assert_eq!(m.to_string(), "{[0, 200): a}");
+    m.insert(Interval::open(199, 300).unwrap(), "a");
+    assert_eq!(m.entries(), &[(iv(0, 300), "a")]);
+
+    // Open ranges with no integers in them are empty.
+    m.insert(Interval::open(10, 11).unwrap(), "b");
+    assert_eq!(m.len(), 1);
+    m.remove(&Interval::closed(10, 10).unwrap());
+    assert_eq!(m.to_string(), "{[0, 10): a, [11, 300): a}");
+
+    // The greatest value has no successor, so its closed bound stays.
+    m.insert(Interval::from(u64::MAX - 1..=u64::MAX), "c");
+    assert_eq!(m.get(&u64::MAX), Some(&"c"));
+
+    // Addresses and characters are discrete too, but floats aren't.
+    let mut hosts = IntervalMap::new();
+    hosts.insert(Interval::closed(Ipv4Addr::new(10, 0, 0, 0), Ipv4Addr::new(10, 0, 0, 255))
+                     .unwrap(), "lab");
+    hosts.insert(Interval::closed(Ipv4Addr::new(10, 0, 1, 0), Ipv4Addr::new(10, 0, 1, 255))
+                     .unwrap(), "lab");
+    assert_eq!(hosts.len(), 1);
+    let letters: IntervalMap<char, &str> =
+        vec![(Interval::from('a'..='m'), "x"), (Interval::from('n'..='z'), "x")]
+            .into_iter().collect();
+    assert_eq!(letters.to_string(), "{[a, {): x}");
+    let floats: IntervalMap<f64, &str> =
+        vec![(Interval::new(0.0, 1.0).unwrap(), "x"), (Interval::open(1.0, 2.0).unwrap(), "x")]
+            .into_iter().collect();
+    assert_eq!(floats.to_string(), "{[0, 1): x, (1, 2): x}");
+}
+
+#[test]
+fn test_lookup() {
+    let mut memory = IntervalMap::new();
+    memory.insert(iv(0x0000, 0x1000), "text");
+    memory.insert(iv(0x1000, 0x1800), "data");
+    memory.insert(iv(0x2000, 0x3000), "heap");
+
+    assert_eq!(memory.get(&0x0fff), Some(&"text"));
+    assert_eq!(memory.get(&0x1000), Some(&"data"));
+    assert_eq!(memory.get(&0x1900), None);
+    assert_eq!(memory.get_entry(&0x2abc), Some((&iv(0x2000, 0x3000), &"heap")));
+
+    let names: Vec<&str> =
+        memory.overlapping(&iv(0x0800, 0x2001)).iter().map(|(_, name)| *name).collect();
+    assert_eq!(names, vec!["text", "data", "heap"]);
+    assert!(memory.overlapping(&iv(0x1800, 0x2000)).is_empty());
+    assert!(memory.overlapping(&iv(0x1000, 0x1000)).is_empty());
+}
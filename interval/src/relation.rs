@@ -0,0 +1,140 @@
+use crate::bound::{cmp_lower, cmp_upper, gap, separated};
+use crate::Interval;
+use std::cmp::Ordering::{Equal, Greater, Less};
+
+/// The thirteen ways two nonempty intervals can be placed relative to each
+/// other, as catalogued by James F. Allen.
+///
+/// Each variant names how `x` relates to `y` in `x.relation(&y)`. The first
+/// six have inverses among the last six, and `Equals` is its own inverse.
+///
+/// Since an `Interval`'s ends may be closed or open, `Meets` means that the
+/// intervals touch without sharing a point, like `[1, 2)` and `[2, 3]`.
+/// Closed intervals sharing an endpoint, like `[1, 2]` and `[2, 3]`, have a
+/// point in common, so they `Overlap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllenRelation {
+    /// `x` ends, and then some time passes before `y` starts.
+    Before,
+    /// `x` ends just as `y` starts.
+    Meets,
+    /// `x` starts first, and `y` starts before `x` ends, and ends after it.
+    Overlaps,
+    /// `x` and `y` start together, and `x` ends first.
+    Starts,
+    /// `x` starts after `y` does, and ends before it.
+    During,
+    /// `x` starts after `y` does, and they end together.
+    Finishes,
+    /// `x` and `y` start and end together.
+    Equals,
+    /// The inverse of `Finishes`.
+    FinishedBy,
+    /// The inverse of `During`.
+    Contains,
+    /// The inverse of `Starts`.
+    StartedBy,
+    /// The inverse of `Overlaps`.
+    OverlappedBy,
+    /// The inverse of `Meets`.
+    MetBy,
+    /// The inverse of `Before`.
+    After,
+}
+
+impl AllenRelation {
+    /// Return the relation `y` has to `x`, given that `x` has `self` to `y`.
+    pub fn inverse(self) -> AllenRelation {
+        use AllenRelation::*;
+        match self {
+            Before => After,
+            Meets => MetBy,
+            Overlaps => OverlappedBy,
+            Starts => StartedBy,
+            During => Contains,
+            Finishes => FinishedBy,
+            Equals => Equals,
+            FinishedBy => Finishes,
+            Contains => During,
+            StartedBy => Starts,
+            OverlappedBy => Overlaps,
+            MetBy => Meets,
+            After => Before,
+        }
+    }
+}
+
+impl<T: PartialOrd> Interval<T> {
+    /// Return the Allen relation `self` has to `other`, or `None` if either
+    /// is empty, or their bounds can't be compared.
+    ///
+    /// This refines `partial_cmp`, which can only say whether one interval
+    /// lies entirely before the other.
+    pub fn relation(&self, other: &Interval<T>) -> Option<AllenRelation> {
+        use AllenRelation::*;
+        if self.is_empty() || other.is_empty() {
+            return None;
+        }
+        if separated(&self.upper, &other.lower) {
+            return Some(if gap(&self.upper, &other.lower) { Before } else { Meets });
+        }
+        if separated(&other.upper, &self.lower) {
+            return Some(if gap(&other.upper, &self.lower) { After } else { MetBy });
+        }
+        let relation = match (cmp_lower(&self.lower, &other.lower)?,
+                              cmp_upper(&self.upper, &other.upper)?) {
+            (Less,    Less)    => Overlaps,
+            (Less,    Equal)   => FinishedBy,
+            (Less,    Greater) => Contains,
+            (Equal,   Less)    => Starts,
+            (Equal,   Equal)   => Equals,
+            (Equal,   Greater) => StartedBy,
+            (Greater, Less)    => During,
+            (Greater, Equal)   => Finishes,
+            (Greater, Greater) => OverlappedBy,
+        };
+        Some(relation)
+    }
+}
+
+#[test]
+fn test_relations() {
+    use AllenRelation::*;
+    use std::ops::Bound::{Excluded, Included};
+
+    let x = Interval::from(10..20);
+    let cases = [
+        (Interval::from(30..40), Before),
+        (Interval::from(20..30), Meets),
+        (Interval::from(15..30), Overlaps),
+        (Interval::from(10..30), Starts),
+        (Interval::from(0..30),  During),
+        (Interval::from(0..20),  Finishes),
+        (Interval::from(10..20), Equals),
+        (Interval::from(15..20), FinishedBy),
+        (Interval::from(12..18), Contains),
+        (Interval::from(10..15), StartedBy),
+        (Interval::from(5..15),  OverlappedBy),
+        (Interval::from(0..10),  MetBy),
+        (Interval::from(0..5),   After),
+    ];
+    for &(y, expected) in &cases {
+        assert_eq!(x.relation(&y), Some(expected), "{} vs {}", x, y);
+        assert_eq!(y.relation(&x), Some(expected.inverse()), "{} vs {}", y, x);
+    }
+
+    // Open and closed ends matter at the boundaries.
+    assert_eq!(Interval::from(0..=10).relation(&Interval::from(10..=20)), Some(Overlaps));
+    assert_eq!(Interval::from(0..=10).relation(&Interval::from((Excluded(10), Included(20)))),
+               Some(Meets));
+    assert_eq!(Interval::open(0, 10).unwrap().relation(&Interval::open(10, 20).unwrap()),
+               Some(Before));
+    assert_eq!(Interval::from(0..=10).relation(&Interval::from(0..10)), Some(StartedBy));
+    assert_eq!(Interval::from(..10).relation(&Interval::from(..)), Some(Starts));
+    assert_eq!(Interval::from(5..).relation(&Interval::from(..)), Some(Finishes));
+
+    assert_eq!(x.relation(&Interval::from(15..15)), None);
+    assert_eq!(Interval::from(0.0..1.0).relation(&Interval::from((Excluded(f64::NAN),
+                                                                  Included(2.0)))),
+               None);
+}
@@ -0,0 +1,73 @@
+//! Finding free time across several calendars.
+
+use crate::{bound_value, Interval, IntervalSet};
+use std::time::{Duration, SystemTime};
+
+/// Return the stretches of time within `window` during which nobody is busy,
+/// and which last at least `min_length`, in order.
+///
+/// Each calendar in `calendars` holds the times one person, or room, or
+/// whatever, is busy. A slot running up against an unbounded end of `window`
+/// is unbounded too, and so always long enough.
+pub fn free_slots<'a, I>(calendars: I, window: &Interval<SystemTime>, min_length: Duration)
+                         -> Vec<Interval<SystemTime>>
+where
+    I: IntoIterator<Item = &'a IntervalSet<SystemTime>>,
+{
+    let busy = calendars
+        .into_iter()
+        .fold(IntervalSet::new(), |busy, calendar| busy.union(calendar));
+    busy.complement(window)
+        .iter()
+        .filter(|slot| long_enough(slot, min_length))
+        .cloned()
+        .collect()
+}
+
+fn long_enough(slot: &Interval<SystemTime>, min_length: Duration) -> bool {
+    match (bound_value(&slot.lower), bound_value(&slot.upper)) {
+        (Some(start), Some(end)) => match end.duration_since(*start) {
+            Ok(length) => length >= min_length,
+            Err(_) => false,
+        },
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+fn at(hour: u64, minute: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(hour * 3600 + minute * 60)
+}
+
+#[cfg(test)]
+fn calendar(meetings: &[(SystemTime, SystemTime)]) -> IntervalSet<SystemTime> {
+    meetings.iter().map(|&(start, end)| Interval::new(start, end).unwrap()).collect()
+}
+
+#[test]
+fn test_free_slots() {
+    let alice = calendar(&[(at(9, 0), at(10, 0)), (at(12, 0), at(13, 0))]);
+    let bob = calendar(&[(at(9, 30), at(10, 30)),
+                         (at(11, 0), at(11, 15)),
+                         (at(16, 0), at(18, 0))]);
+    let room = calendar(&[(at(14, 0), at(14, 40))]);
+    let day = Interval::new(at(9, 0), at(17, 0)).unwrap();
+
+    let half_hour = Duration::from_secs(30 * 60);
+    let slots = free_slots(&[alice, bob, room], &day, half_hour);
+    assert_eq!(slots, vec![
+        Interval::new(at(10, 30), at(11, 0)).unwrap(),
+        Interval::new(at(11, 15), at(12, 0)).unwrap(),
+        Interval::new(at(13, 0), at(14, 0)).unwrap(),
+        Interval::new(at(14, 40), at(16, 0)).unwrap(),
+    ]);
+
+    // Nobody busy: the whole window is free. An unbounded window gives
+    // unbounded slots.
+    assert_eq!(free_slots(&[], &day, half_hour), vec![day]);
+    let everyone = calendar(&[(at(9, 0), at(17, 0))]);
+    let after_nine = Interval::from(at(9, 0)..);
+    assert_eq!(free_slots(Some(&everyone), &after_nine, Duration::from_secs(86400)),
+               vec![Interval::from(at(17, 0)..)]);
+    assert!(free_slots(Some(&everyone), &day, Duration::from_secs(0)).is_empty());
+}
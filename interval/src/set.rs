@@ -0,0 +1,338 @@
+use crate::bound::{below_upper, cmp_upper, complement, gap, max_upper, min_lower, separated};
+use crate::map::{normalize, MapKey};
+use crate::Interval;
+use std::fmt;
+use std::iter::FromIterator;
+
+/// A set of values, represented as a sorted list of disjoint intervals.
+///
+/// The list is kept normalized: no interval is empty, and no two overlap or
+/// even touch, since touching intervals like `[1, 2)` and `[2, 3]` are merged
+/// into one. So two sets containing the same values always have the same
+/// intervals, and compare equal.
+///
+/// As in `IntervalMap`, discrete elements like integers are stored in the
+/// half-open form `[a, b)`, so that `[0, 5]` and `[6, 10]` count as touching.
+/// See `MapKey`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { intervals: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Return the set's intervals, in increasing order.
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Interval<T>> {
+        self.intervals.iter()
+    }
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet::new()
+    }
+}
+
+impl<T: MapKey> IntervalSet<T> {
+    /// Add the values in `interval` to the set, merging it with any intervals
+    /// it overlaps or touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let interval = normalize(&interval);
+
+        // The intervals from `start` to `end` overlap or touch the new one.
+        let start = self.intervals.partition_point(|i| gap(&i.upper, &interval.lower));
+        let end = self.intervals.partition_point(|i| !gap(&interval.upper, &i.lower));
+
+        let mut merged = interval;
+        if start < end {
+            merged.lower = min_lower(&self.intervals[start].lower, &merged.lower).clone();
+            merged.upper = max_upper(&self.intervals[end - 1].upper, &merged.upper).clone();
+        }
+        self.intervals.splice(start..end, Some(merged));
+    }
+
+    /// Remove the values in `interval` from the set, splitting any interval
+    /// that extends past it on both sides.
+    pub fn remove(&mut self, interval: &Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let interval = &normalize(interval);
+
+        // The intervals from `start` to `end` overlap the one being removed.
+        let start = self.intervals.partition_point(|i| separated(&i.upper, &interval.lower));
+        let end = self.intervals.partition_point(|i| !separated(&interval.upper, &i.lower));
+        if start >= end {
+            return;
+        }
+
+        // Keep whatever sticks out on either side. An unbounded end of
+        // `interval` leaves nothing on that side.
+        let mut remnants = Vec::with_capacity(2);
+        if let Some(upper) = complement(&interval.lower) {
+            let before = Interval { lower: self.intervals[start].lower.clone(), upper };
+            if !before.is_empty() {
+                remnants.push(before);
+            }
+        }
+        if let Some(lower) = complement(&interval.upper) {
+            let after = Interval { lower, upper: self.intervals[end - 1].upper.clone() };
+            if !after.is_empty() {
+                remnants.push(after);
+            }
+        }
+        self.intervals.splice(start..end, remnants);
+    }
+
+    /// Return true if `value` is in the set.
+    pub fn contains(&self, value: &T) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Return the interval containing `value`, if any.
+    pub fn find(&self, value: &T) -> Option<&Interval<T>> {
+        let index = self.intervals.partition_point(|i| !below_upper(&i.upper, value));
+        self.intervals.get(index).filter(|i| i.contains(value))
+    }
+
+    /// Return the values in `universe` that are not in this set.
+    pub fn complement(&self, universe: &Interval<T>) -> IntervalSet<T> {
+        let mut complement = IntervalSet::new();
+        complement.insert(universe.clone());
+        for interval in &self.intervals {
+            complement.remove(interval);
+        }
+        complement
+    }
+
+    /// Return the values in either `self` or `other`.
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut union = self.clone();
+        for interval in &other.intervals {
+            union.insert(interval.clone());
+        }
+        union
+    }
+
+    /// Return the values in both `self` and `other`.
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        // Walk both lists together, always advancing past whichever current
+        // interval ends first, since it can't overlap anything further on.
+        let mut intersection = Vec::new();
+        let mut a = self.intervals.iter().peekable();
+        let mut b = other.intervals.iter().peekable();
+        while let (Some(x), Some(y)) = (a.peek(), b.peek()) {
+            if let Some(common) = x.intersection(y) {
+                intersection.push(common);
+            }
+            if cmp_upper(&x.upper, &y.upper) != Some(std::cmp::Ordering::Greater) {
+                a.next();
+            } else {
+                b.next();
+            }
+        }
+
+        // The pieces are disjoint, and separated by gaps in one set or the
+        // other, so they're already normalized.
+        IntervalSet { intervals: intersection }
+    }
+}
+
+impl<T: MapKey> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: MapKey> Extend<Interval<T>> for IntervalSet<T> {
+    fn extend<I: IntoIterator<Item = Interval<T>>>(&mut self, iter: I) {
+        for interval in iter {
+            self.insert(interval);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IntervalSet<T> {
+    type Item = &'a Interval<T>;
+    type IntoIter = std::slice::Iter<'a, Interval<T>>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.intervals.iter()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for IntervalSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, interval) in self.intervals.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", interval)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+#[cfg(test)]
+fn set(pairs: &[(u64, u64)]) -> IntervalSet<u64> {
+    pairs.iter().map(|&(l, u)| Interval::new(l, u).unwrap()).collect()
+}
+
+#[cfg(test)]
+fn iv(lower: u64, upper: u64) -> Interval<u64> {
+    Interval::new(lower, upper).unwrap()
+}
+
+#[test]
+fn test_insert() {
+    let mut s = IntervalSet::new();
+    s.insert(iv(10, 20));
+    s.insert(iv(30, 40));
+    s.insert(iv(0, 5));
+    assert_eq!(s.intervals(), &[iv(0, 5), iv(10, 20), iv(30, 40)]);
+
+    // Touching intervals merge; empty ones are ignored.
+    s.insert(iv(20, 25));
+    s.insert(iv(50, 50));
+    assert_eq!(s.intervals(), &[iv(0, 5), iv(10, 25), iv(30, 40)]);
+
+    // An interval spanning several merges them all.
+    s.insert(iv(3, 35));
+    assert_eq!(s.intervals(), &[iv(0, 40)]);
+
+    // Inserting something already covered changes nothing.
+    s.insert(iv(12, 13));
+    assert_eq!(s, set(&[(0, 40)]));
+}
+
+#[test]
+fn test_remove() {
+    let mut s = set(&[(0, 100)]);
+    s.remove(&iv(40, 60));
+    assert_eq!(s.intervals(), &[iv(0, 40), iv(60, 100)]);
+
+    s.remove(&iv(30, 70));
+    assert_eq!(s.intervals(), &[iv(0, 30), iv(70, 100)]);
+
+    s.remove(&iv(0, 10));
+    s.remove(&iv(95, 200));
+    s.remove(&iv(50, 60));
+    assert_eq!(s.intervals(), &[iv(10, 30), iv(70, 95)]);
+
+    s.remove(&iv(0, 1000));
+    assert!(s.is_empty());
+}
+
+#[test]
+fn test_lookup() {
+    let s = set(&[(0, 10), (20, 30)]);
+    assert!(s.contains(&0));
+    assert!(s.contains(&25));
+    assert!(!s.contains(&10));
+    assert!(!s.contains(&15));
+    assert!(!s.contains(&30));
+    assert_eq!(s.find(&29), Some(&iv(20, 30)));
+    assert_eq!(s.find(&31), None);
+}
+
+#[test]
+fn test_set_operations() {
+    // Free and used blocks on a 100-block disk.
+    let used = set(&[(0, 10), (20, 30), (90, 100)]);
+    let free = used.complement(&iv(0, 100));
+    assert_eq!(free, set(&[(10, 20), (30, 90)]));
+    assert_eq!(free.union(&used), set(&[(0, 100)]));
+    assert!(free.intersection(&used).is_empty());
+
+    let a = set(&[(0, 10), (20, 30), (40, 50)]);
+    let b = set(&[(5, 25), (45, 60)]);
+    assert_eq!(a.union(&b), set(&[(0, 30), (40, 60)]));
+    assert_eq!(a.intersection(&b), set(&[(5, 10), (20, 25), (45, 50)]));
+    assert_eq!(b.intersection(&a), a.intersection(&b));
+    assert_eq!(a.complement(&iv(5, 45)), set(&[(10, 20), (30, 40)]));
+
+    assert_eq!(a.to_string(), "{[0, 10), [20, 30), [40, 50)}");
+}
+
+#[test]
+fn test_mixed_bounds() {
+    use std::ops::Bound::{Excluded, Included};
+
+    // For continuous values, [0, 5] and (5, 10) touch, so they merge; (5, 10)
+    // and (10, 20) leave 10 out, so they don't.
+    let mut s = IntervalSet::new();
+    s.insert(Interval::closed(0.0, 5.0).unwrap());
+    s.insert(Interval::open(5.0, 10.0).unwrap());
+    s.insert(Interval::open(10.0, 20.0).unwrap());
+    assert_eq!(s.to_string(), "{[0, 10), (10, 20)}");
+    assert!(!s.contains(&10.0));
+    s.insert(Interval::closed(10.0, 10.0).unwrap());
+    assert_eq!(s.to_string(), "{[0, 20)}");
+
+    // Removing a closed interval leaves open ends behind.
+    s.remove(&Interval::closed(5.0, 8.0).unwrap());
+    assert_eq!(s.to_string(), "{[0, 5), (8, 20)}");
+    s.remove(&Interval::from((Excluded(15.0), Included(20.0))));
+    assert_eq!(s.to_string(), "{[0, 5), (8, 15]}");
+
+    // Unbounded intervals.
+    s.insert(Interval::from(100.0..));
+    assert!(s.contains(&f64::MAX));
+    s.remove(&Interval::from(..3.0));
+    assert_eq!(s.to_string(), "{[3, 5), (8, 15], [100, ∞)}");
+    assert_eq!(s.complement(&Interval::from(..)).to_string(),
+               "{(-∞, 3), [5, 8], (15, 100)}");
+    let other: IntervalSet<f64> = vec![Interval::new(4.0, 10.0).unwrap(),
+                                       Interval::new(12.0, 200.0).unwrap()]
+        .into_iter()
+        .collect();
+    assert_eq!(s.intersection(&other).to_string(),
+               "{[4, 5), (8, 10), [12, 15], [100, 200)}");
+}
+
+#[test]
+fn test_discrete_bounds() {
+    use std::ops::Bound::{Excluded, Included};
+
+    // Closed integer ranges with nothing between them touch, and merge.
+    let mut s = IntervalSet::new();
+    s.insert(Interval::closed(0u64, 5).unwrap());
+    s.insert(Interval::closed(6, 10).unwrap());
+    assert_eq!(s.intervals(), &[iv(0, 11)]);
+    assert_eq!(s, set(&[(0, 11)]));
+
+    // Open ends are rewritten too, so (5, 10) and (10, 20) leave just 10 out.
+    let mut s = IntervalSet::new();
+    s.insert(Interval::open(5u64, 10).unwrap());
+    s.insert(Interval::open(10, 20).unwrap());
+    assert_eq!(s.to_string(), "{[6, 10), [11, 20)}");
+    s.insert(Interval::closed(10, 10).unwrap());
+    assert_eq!(s, set(&[(6, 20)]));
+
+    // Removing a closed range leaves half-open remnants.
+    s.remove(&Interval::closed(8, 12).unwrap());
+    assert_eq!(s, set(&[(6, 8), (13, 20)]));
+    s.remove(&Interval::from((Excluded(15), Included(19))));
+    assert_eq!(s, set(&[(6, 8), (13, 16)]));
+
+    // A bound at the top of the type's range stays as it is.
+    s.insert(Interval::closed(100, u64::MAX).unwrap());
+    assert!(s.contains(&u64::MAX));
+    assert_eq!(s.to_string(), "{[6, 8), [13, 16), [100, 18446744073709551615]}");
+}
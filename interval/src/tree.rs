@@ -0,0 +1,519 @@
+use crate::bound::{above_lower, below_upper, cmp_lower, cmp_upper, separated};
+use crate::Interval;
+use std::cmp::Ordering::{self, Equal, Greater};
+use std::iter::FromIterator;
+use std::mem;
+#[cfg(test)]
+use std::ops::Bound;
+
+/// A collection of intervals, supporting quick searches for the intervals
+/// that contain a given point, or overlap a given interval. The tree may hold
+/// several copies of the same interval.
+///
+/// This is a priority search tree: a binary search tree ordered by lower
+/// bound, then upper bound, that is also a heap ordered by upper bound. Each
+/// node holds the interval with the greatest upper bound in its subtree, and a
+/// split that sends the rest of the subtree's intervals left or right. A
+/// search can skip any subtree whose top interval ends below the point sought,
+/// and any right subtree whose intervals all begin above it. Every node a
+/// search visits then either reports an interval, is a child of one that
+/// does, or lies on the path to the point itself, so reporting `k` intervals
+/// takes `O(log n + k)` time.
+///
+/// To stay shallow, the tree rebuilds any subtree that insertion leaves too
+/// lopsided, as a scapegoat tree does, and rebuilds everything once enough
+/// intervals have been removed. Insertion and removal take `O(log² n)`
+/// amortized time.
+///
+/// `PartialOrd` on `Interval` can't order a tree like this, since it leaves
+/// overlapping intervals unordered, so the tree uses its own ordering. `T`
+/// need only be `PartialOrd`, so that intervals of `f64` work, but the bounds
+/// stored must be comparable with each other; `insert` refuses bounds like
+/// NaN that aren't even comparable with themselves.
+pub struct IntervalTree<T> {
+    root: Link<T>,
+    len: usize,
+    /// The most nodes the tree has had since it was last rebuilt.
+    max_nodes: usize,
+}
+
+type Link<T> = Option<Box<Node<T>>>;
+
+struct Node<T> {
+    /// The interval with the greatest upper bound in this subtree.
+    interval: Interval<T>,
+    /// How many copies of `interval` the tree holds.
+    count: usize,
+    /// Intervals ordered at or before `split` are in the left subtree, and
+    /// later ones in the right. This need not be an interval the tree holds.
+    split: Interval<T>,
+    /// The number of nodes in this subtree.
+    size: usize,
+    left: Link<T>,
+    right: Link<T>,
+}
+
+/// The order in which the tree keeps its intervals. `insert` only accepts
+/// intervals whose bounds are comparable, so this is a total order on them.
+fn compare<T: PartialOrd>(a: &Interval<T>, b: &Interval<T>) -> Ordering {
+    cmp_lower(&a.lower, &b.lower).unwrap_or(Equal)
+        .then_with(|| cmp_upper(&a.upper, &b.upper).unwrap_or(Equal))
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+/// The deepest a node may be in a tree of `nodes` nodes before some subtree
+/// above it must be rebuilt: `log₄/₃ nodes`.
+fn depth_limit(nodes: usize) -> usize {
+    ((nodes as f64).ln() / (4.0f64 / 3.0).ln()) as usize
+}
+
+/// Return the child of `node` that `interval` belongs in.
+fn child_for<'a, T: PartialOrd>(node: &'a mut Node<T>, interval: &Interval<T>) -> &'a mut Link<T> {
+    if compare(interval, &node.split) == Greater {
+        &mut node.right
+    } else {
+        &mut node.left
+    }
+}
+
+/// Return the node holding `interval` in the subtree at `link`, if any. An
+/// interval only ever moves along the path a search for it follows.
+fn find_mut<'a, T: PartialOrd>(link: &'a mut Link<T>, interval: &Interval<T>)
+                               -> Option<&'a mut Node<T>> {
+    let node = link.as_deref_mut()?;
+    if compare(&node.interval, interval) == Equal {
+        return Some(node);
+    }
+    find_mut(child_for(node, interval), interval)
+}
+
+/// Insert `count` copies of `interval`, which the subtree at `link` doesn't
+/// hold, `depth` levels below the root. Return true if the new node is deeper
+/// than `limit` and no subtree on the way has been rebuilt yet.
+fn insert<T: PartialOrd + Clone>(link: &mut Link<T>, mut interval: Interval<T>, mut count: usize,
+                                 depth: usize, limit: usize) -> bool {
+    let node = match link {
+        None => {
+            *link = Some(Box::new(Node {
+                split: interval.clone(),
+                interval,
+                count,
+                size: 1,
+                left: None,
+                right: None,
+            }));
+            return depth > limit;
+        }
+        Some(node) => node,
+    };
+    node.size += 1;
+    // Whichever interval ends later stays here; the other goes further down.
+    if cmp_upper(&interval.upper, &node.interval.upper) == Some(Greater) {
+        mem::swap(&mut interval, &mut node.interval);
+        mem::swap(&mut count, &mut node.count);
+    }
+    let child = child_for(node, &interval);
+    let too_deep = insert(child, interval, count, depth + 1, limit);
+    if too_deep && 4 * size(child) > 3 * node.size {
+        rebuild(link);
+        return false;
+    }
+    too_deep
+}
+
+/// Remove the node holding `interval`, which must be present, from the
+/// subtree at `link`.
+fn remove<T: PartialOrd>(link: &mut Link<T>, interval: &Interval<T>) {
+    let node = link.as_mut().expect("interval to remove is missing");
+    if compare(&node.interval, interval) != Equal {
+        node.size -= 1;
+        return remove(child_for(node, interval), interval);
+    }
+    vacate(link);
+}
+
+/// Discard the interval held by the node at `link`, and refill the node from
+/// below, or remove it if it has no children.
+fn vacate<T: PartialOrd>(link: &mut Link<T>) {
+    let Node { interval, count, size, left, right, .. } =
+        &mut **link.as_mut().expect("no node to vacate");
+    let child = match (left.as_ref(), right.as_ref()) {
+        (None, None) => {
+            *link = None;
+            return;
+        }
+        (Some(l), Some(r)) if cmp_upper(&r.interval.upper, &l.interval.upper) == Some(Greater) => {
+            right
+        }
+        (Some(_), _) => left,
+        (None, Some(_)) => right,
+    };
+    *size -= 1;
+    let below = child.as_mut().unwrap();
+    mem::swap(interval, &mut below.interval);
+    mem::swap(count, &mut below.count);
+    vacate(child);
+}
+
+/// Rebuild the subtree at `link` to be as shallow as possible.
+fn rebuild<T: PartialOrd + Clone>(link: &mut Link<T>) {
+    fn collect<T>(link: Link<T>, entries: &mut Vec<(Interval<T>, usize)>) {
+        if let Some(node) = link {
+            let Node { interval, count, left, right, .. } = *node;
+            entries.push((interval, count));
+            collect(left, entries);
+            collect(right, entries);
+        }
+    }
+
+    let mut entries = Vec::with_capacity(size(link));
+    collect(link.take(), &mut entries);
+    entries.sort_by(|a, b| compare(&a.0, &b.0));
+    *link = build(entries);
+}
+
+/// Build a tree holding `entries`, which must be sorted by `compare`.
+fn build<T: PartialOrd + Clone>(mut entries: Vec<(Interval<T>, usize)>) -> Link<T> {
+    if entries.is_empty() {
+        return None;
+    }
+    let top = (1..entries.len()).fold(0, |top, i| {
+        if cmp_upper(&entries[i].0.upper, &entries[top].0.upper) == Some(Greater) { i } else { top }
+    });
+    let (interval, count) = entries.remove(top);
+    let right = entries.split_off(entries.len().div_ceil(2));
+    let split = entries.last().map_or_else(|| interval.clone(), |(last, _)| last.clone());
+    Some(Box::new(Node {
+        interval,
+        count,
+        split,
+        size: 1 + entries.len() + right.len(),
+        left: build(entries),
+        right: build(right),
+    }))
+}
+
+impl<T: PartialOrd + Clone> IntervalTree<T> {
+    pub fn new() -> IntervalTree<T> {
+        IntervalTree { root: None, len: 0, max_nodes: 0 }
+    }
+
+    /// Return the number of intervals in the tree, counting each copy.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Add `interval` to the tree, even if it already holds a copy. Return
+    /// false, leaving the tree unchanged, if `interval`'s bounds aren't
+    /// comparable, as when they're NaN.
+    pub fn insert(&mut self, interval: Interval<T>) -> bool {
+        if cmp_lower(&interval.lower, &interval.lower).is_none()
+            || cmp_upper(&interval.upper, &interval.upper).is_none()
+        {
+            return false;
+        }
+        self.len += 1;
+        if let Some(node) = find_mut(&mut self.root, &interval) {
+            node.count += 1;
+            return true;
+        }
+        let nodes = size(&self.root) + 1;
+        insert(&mut self.root, interval, 1, 0, depth_limit(nodes));
+        self.max_nodes = self.max_nodes.max(nodes);
+        true
+    }
+
+    /// Remove one copy of `interval` from the tree. Return false if it wasn't
+    /// present.
+    pub fn remove(&mut self, interval: &Interval<T>) -> bool {
+        match find_mut(&mut self.root, interval) {
+            None => return false,
+            Some(node) if node.count > 1 => node.count -= 1,
+            Some(_) => {
+                remove(&mut self.root, interval);
+                let nodes = size(&self.root);
+                if 4 * nodes < 3 * self.max_nodes {
+                    rebuild(&mut self.root);
+                    self.max_nodes = nodes;
+                }
+            }
+        }
+        self.len -= 1;
+        true
+    }
+
+    /// Return the stored intervals that contain `point`, in no particular
+    /// order.
+    pub fn stab(&self, point: &T) -> Vec<&Interval<T>> {
+        fn visit<'a, T: PartialOrd>(link: &'a Link<T>, point: &T,
+                                    found: &mut Vec<&'a Interval<T>>) {
+            // Nothing below ends later than this node's interval.
+            let node = match link {
+                Some(node) if below_upper(&node.interval.upper, point) => node,
+                _ => return,
+            };
+            if node.interval.contains(point) {
+                found.extend(std::iter::repeat_n(&node.interval, node.count));
+            }
+            visit(&node.left, point, found);
+            // Everything to the right begins at or after the split.
+            if above_lower(&node.split.lower, point) {
+                visit(&node.right, point, found);
+            }
+        }
+
+        let mut found = Vec::new();
+        visit(&self.root, point, &mut found);
+        found
+    }
+
+    /// Return the stored intervals that overlap `query`, in no particular
+    /// order.
+    pub fn overlapping(&self, query: &Interval<T>) -> Vec<&Interval<T>> {
+        fn visit<'a, T: PartialOrd>(link: &'a Link<T>, query: &Interval<T>,
+                                    found: &mut Vec<&'a Interval<T>>) {
+            let node = match link {
+                Some(node) if !separated(&node.interval.upper, &query.lower) => node,
+                _ => return,
+            };
+            if node.interval.overlaps(query) {
+                found.extend(std::iter::repeat_n(&node.interval, node.count));
+            }
+            visit(&node.left, query, found);
+            if !separated(&query.upper, &node.split.lower) {
+                visit(&node.right, query, found);
+            }
+        }
+
+        let mut found = Vec::new();
+        if !query.is_empty() {
+            visit(&self.root, query, &mut found);
+        }
+        found
+    }
+
+    /// Return an iterator over the stored intervals, each copy separately, in
+    /// no particular order.
+    pub fn iter(&self) -> IntervalTreeIter<'_, T> {
+        IntervalTreeIter { stack: self.root.as_deref().into_iter().collect(), current: None }
+    }
+}
+
+impl<T: PartialOrd + Clone> Default for IntervalTree<T> {
+    fn default() -> Self {
+        IntervalTree::new()
+    }
+}
+
+/// An iterator over an `IntervalTree`.
+pub struct IntervalTreeIter<'a, T> {
+    /// Nodes not yet visited.
+    stack: Vec<&'a Node<T>>,
+    /// The interval being produced, and how many copies of it remain.
+    current: Option<(&'a Interval<T>, usize)>,
+}
+
+impl<'a, T> Iterator for IntervalTreeIter<'a, T> {
+    type Item = &'a Interval<T>;
+    fn next(&mut self) -> Option<&'a Interval<T>> {
+        loop {
+            if let Some((interval, remaining)) = &mut self.current {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Some(interval);
+                }
+            }
+            let node = self.stack.pop()?;
+            self.stack.extend(node.left.as_deref());
+            self.stack.extend(node.right.as_deref());
+            self.current = Some((&node.interval, node.count));
+        }
+    }
+}
+
+impl<'a, T: PartialOrd + Clone> IntoIterator for &'a IntervalTree<T> {
+    type Item = &'a Interval<T>;
+    type IntoIter = IntervalTreeIter<'a, T>;
+    fn into_iter(self) -> IntervalTreeIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: PartialOrd + Clone> FromIterator<Interval<T>> for IntervalTree<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut tree = IntervalTree::new();
+        for interval in iter {
+            tree.insert(interval);
+        }
+        tree
+    }
+}
+
+#[cfg(test)]
+fn iv(lower: i32, upper: i32) -> Interval<i32> {
+    Interval::new(lower, upper).unwrap()
+}
+
+/// Sort query results into the tree's order, so they can be compared.
+#[cfg(test)]
+fn sorted<'a, T: PartialOrd>(intervals: impl IntoIterator<Item = &'a Interval<T>>)
+                             -> Vec<&'a Interval<T>> {
+    let mut intervals: Vec<_> = intervals.into_iter().collect();
+    intervals.sort_by(|a, b| compare(a, b));
+    intervals
+}
+
+/// Check the search tree, heap and size invariants of the subtree at `link`,
+/// whose intervals must fall after `after` and at or before `upto`. Return
+/// the subtree's height.
+#[cfg(test)]
+fn check<T: PartialOrd>(link: &Link<T>, after: Option<&Interval<T>>, upto: Option<&Interval<T>>)
+                        -> usize {
+    let node = match link {
+        None => return 0,
+        Some(node) => node,
+    };
+    assert!(node.count > 0);
+    assert!(after.is_none_or(|after| compare(&node.interval, after) == Greater));
+    assert!(upto.is_none_or(|upto| compare(&node.interval, upto) != Greater));
+    for child in [&node.left, &node.right].iter().copied().flatten() {
+        assert_ne!(cmp_upper(&child.interval.upper, &node.interval.upper), Some(Greater));
+    }
+    assert_eq!(node.size, 1 + size(&node.left) + size(&node.right));
+    let left = check(&node.left, after, Some(&node.split));
+    let right = check(&node.right, Some(&node.split), upto);
+    1 + left.max(right)
+}
+
+#[test]
+fn test_insert_remove() {
+    let mut tree = IntervalTree::new();
+    for i in 0..100 {
+        assert!(tree.insert(iv(i, i + 10)));
+        check(&tree.root, None, None);
+    }
+    assert_eq!(tree.len(), 100);
+    assert!(sorted(&tree).into_iter().map(|i| i.lower).eq((0..100).map(Bound::Included)));
+    // Even inserted in order, the tree stays shallow.
+    assert!(check(&tree.root, None, None) <= depth_limit(100) + 1);
+
+    for i in (0..100).step_by(3) {
+        assert!(tree.remove(&iv(i, i + 10)));
+        check(&tree.root, None, None);
+    }
+    assert!(!tree.remove(&iv(0, 10)));
+    assert_eq!(tree.len(), 66);
+    assert!(sorted(&tree)
+                .into_iter()
+                .map(|i| i.lower)
+                .eq((0..100).filter(|i| i % 3 != 0).map(Bound::Included)));
+    assert!(check(&tree.root, None, None) <= depth_limit(66) + 1);
+}
+
+#[test]
+fn test_duplicates() {
+    let mut tree = IntervalTree::new();
+    assert!(tree.insert(iv(1, 5)));
+    assert!(tree.insert(iv(1, 5)));
+    assert!(tree.insert(iv(2, 3)));
+    check(&tree.root, None, None);
+    assert_eq!(tree.len(), 3);
+    assert_eq!(sorted(tree.stab(&2)), vec![&iv(1, 5), &iv(1, 5), &iv(2, 3)]);
+    assert_eq!(tree.overlapping(&iv(4, 9)), vec![&iv(1, 5), &iv(1, 5)]);
+
+    assert!(tree.remove(&iv(1, 5)));
+    assert_eq!(tree.len(), 2);
+    assert_eq!(tree.stab(&4), vec![&iv(1, 5)]);
+    assert!(tree.remove(&iv(1, 5)));
+    assert!(!tree.remove(&iv(1, 5)));
+    assert_eq!(tree.iter().collect::<Vec<_>>(), vec![&iv(2, 3)]);
+}
+
+#[test]
+fn test_queries() {
+    let intervals = vec![iv(0, 10), iv(5, 7), iv(8, 20), iv(15, 16), iv(30, 40), iv(6, 6)];
+    let tree: IntervalTree<i32> = intervals.into_iter().collect();
+
+    assert_eq!(sorted(tree.stab(&6)), vec![&iv(0, 10), &iv(5, 7)]);
+    assert_eq!(sorted(tree.stab(&9)), vec![&iv(0, 10), &iv(8, 20)]);
+    assert_eq!(tree.stab(&20), Vec::<&Interval<i32>>::new());
+    assert_eq!(tree.stab(&35), vec![&iv(30, 40)]);
+
+    assert_eq!(sorted(tree.overlapping(&iv(7, 16))), vec![&iv(0, 10), &iv(8, 20), &iv(15, 16)]);
+    assert_eq!(tree.overlapping(&iv(20, 30)), Vec::<&Interval<i32>>::new());
+    assert_eq!(tree.overlapping(&iv(12, 12)), Vec::<&Interval<i32>>::new());
+    assert_eq!(tree.overlapping(&iv(-5, 100)).len(), 5);
+}
+
+#[test]
+fn test_queries_against_brute_force() {
+    // A deterministic jumble of intervals, some of them duplicates.
+    let intervals: Vec<Interval<i32>> = (0..500)
+        .map(|i: i32| {
+            let lower = (i * 7919) % 1000;
+            iv(lower, lower + (i * 31) % 50)
+        })
+        .collect();
+    let mut tree: IntervalTree<i32> = intervals.iter().cloned().collect();
+    let mut stored = intervals.clone();
+    for i in intervals.iter().step_by(4) {
+        assert!(tree.remove(i));
+        let index = stored.iter().position(|s| s == i).unwrap();
+        stored.remove(index);
+    }
+    check(&tree.root, None, None);
+    assert_eq!(sorted(&tree), sorted(&stored));
+
+    for point in (-10..1060).step_by(7) {
+        let expected = sorted(stored.iter().filter(|i| i.contains(&point)));
+        assert_eq!(sorted(tree.stab(&point)), expected);
+
+        let query = iv(point, point + 25);
+        let expected = sorted(stored.iter().filter(|i| i.overlaps(&query)));
+        assert_eq!(sorted(tree.overlapping(&query)), expected);
+    }
+}
+
+#[test]
+fn test_mixed_bounds() {
+    let tree: IntervalTree<i32> = vec![
+        Interval::closed(0, 10).unwrap(),
+        Interval::open(10, 20).unwrap(),
+        Interval::from(15..),
+        Interval::from(..=0),
+    ].into_iter().collect();
+    check(&tree.root, None, None);
+
+    assert_eq!(sorted(tree.stab(&0)),
+               vec![&Interval::from(..=0), &Interval::closed(0, 10).unwrap()]);
+    assert_eq!(tree.stab(&10), vec![&Interval::closed(0, 10).unwrap()]);
+    assert_eq!(tree.stab(&1000), vec![&Interval::from(15..)]);
+    assert_eq!(tree.overlapping(&Interval::closed(20, 20).unwrap()),
+               vec![&Interval::from(15..)]);
+    assert_eq!(tree.overlapping(&Interval::from(..)).len(), 4);
+}
+
+#[test]
+fn test_floats() {
+    let mut tree = IntervalTree::new();
+    assert!(tree.insert(Interval::new(0.0, 1.5).unwrap()));
+    assert!(tree.insert(Interval::closed(1.5, 2.5).unwrap()));
+    assert!(tree.insert(Interval::from(2.0..)));
+    assert!(!tree.insert(Interval::from(f64::NAN..1.0)));
+    assert!(!tree.insert(Interval::from(..f64::NAN)));
+    assert_eq!(tree.len(), 3);
+    check(&tree.root, None, None);
+
+    assert_eq!(tree.stab(&1.5), vec![&Interval::closed(1.5, 2.5).unwrap()]);
+    assert_eq!(tree.stab(&2.25).len(), 2);
+    assert!(tree.stab(&f64::NAN).is_empty());
+    assert_eq!(sorted(tree.overlapping(&Interval::new(1.0, 2.0).unwrap())),
+               vec![&Interval::new(0.0, 1.5).unwrap(), &Interval::closed(1.5, 2.5).unwrap()]);
+}
@@ -35,6 +35,8 @@ impl Queue {
 }
 
 #[test]
+// Spells out the borrows that method calls make implicitly.
+#[allow(clippy::needless_borrow)]
 fn test_push_pop() {
     let mut q = Queue { older: Vec::new(), younger: Vec::new() };
 
@@ -110,3 +112,83 @@ fn test_new() {
     assert_eq!(q.pop(), Some('∞'));
     assert_eq!(q.pop(), None);
 }
+
+impl Default for Queue {
+    fn default() -> Queue {
+        Queue::new()
+    }
+}
+
+/// Writing to a queue pushes the characters written, so `write!` can feed a
+/// queue directly.
+impl std::fmt::Write for Queue {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.younger.extend(s.chars());
+        Ok(())
+    }
+
+    fn write_char(&mut self, c: char) -> std::fmt::Result {
+        self.push(c);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_fmt_write() {
+    use std::fmt::Write;
+
+    let mut q = Queue::default();
+    let (n, c) = (2, 'π');
+    write!(q, "{} + {}", n, c).unwrap();
+    q.write_char('!').unwrap();
+    let mut s = String::new();
+    while let Some(c) = q.pop() {
+        s.push(c);
+    }
+    assert_eq!(s, "2 + π!");
+}
+
+impl Queue {
+    /// Pop characters and append them to `buf` until we pop `delim` or the
+    /// queue runs out, whichever comes first. The delimiter, if found, is
+    /// appended too. Like `BufRead::read_until`, return the number of bytes
+    /// appended, so that zero means the queue was empty.
+    pub fn read_until(&mut self, delim: char, buf: &mut String) -> usize {
+        let start = buf.len();
+        while let Some(c) = self.pop() {
+            buf.push(c);
+            if c == delim {
+                break;
+            }
+        }
+        buf.len() - start
+    }
+
+    /// Pop a line of characters, including its terminating newline if there
+    /// is one, and append it to `buf`. Return the number of bytes appended.
+    pub fn read_line(&mut self, buf: &mut String) -> usize {
+        self.read_until('\n', buf)
+    }
+}
+
+#[test]
+fn test_read_line() {
+    use std::fmt::Write;
+
+    // A tokenizer writes into the queue, and a lexer reads lines back out.
+    let mut q = Queue::new();
+    writeln!(q, "let x = 1;").unwrap();
+    write!(q, "x → ∞").unwrap();
+
+    let mut line = String::new();
+    assert_eq!(q.read_line(&mut line), 11);
+    assert_eq!(line, "let x = 1;\n");
+
+    line.clear();
+    assert_eq!(q.read_until(' ', &mut line), 2);
+    assert_eq!(line, "x ");
+    assert_eq!(q.read_line(&mut line), 7);
+    assert_eq!(line, "x → ∞");
+    assert_eq!(q.read_line(&mut line), 0);
+    assert!(q.is_empty());
+}